@@ -12,6 +12,57 @@ struct CWrapperArgs {
     create: bool,
     free: bool,
     unwrap: bool,
+    refcounted: bool,
+    depends_on: Vec<syn::LitStr>,
+    sync: Option<String>,
+    borrow_check: bool,
+    validate: bool,
+    handles: Option<String>,
+    type_name: bool,
+    is_empty: bool,
+    same_type: bool,
+    clone: Vec<syn::LitStr>,
+    dtype: Vec<syn::LitStr>,
+    deprecated: Option<String>,
+    since: Option<String>,
+}
+
+// Creates the attributes for the `deprecated` and `since` options of a generated function.
+// These must be placed after the documentation of the function.
+pub(crate) fn create_version_attributes(
+    deprecated: &Option<String>,
+    since: &Option<String>,
+) -> proc_macro2::TokenStream {
+    let mut attributes = quote! {};
+    if let Some(since) = since {
+        let doc = format!(" Available since version {}.", since);
+        attributes.extend(quote! {
+            #[doc = ""]
+            #[doc = #doc]
+        });
+    }
+    if let Some(note) = deprecated {
+        attributes.extend(quote! {
+            #[deprecated(note = #note)]
+        });
+    }
+    attributes
+}
+
+// Converts the name of a wrapper struct to the snake case used for the names of C functions.
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
 }
 
 pub(crate) fn c_wrapper_impl(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -67,6 +118,19 @@ pub(crate) fn c_wrapper_impl(args: TokenStream, item: TokenStream) -> TokenStrea
         create,
         free,
         unwrap,
+        refcounted,
+        depends_on,
+        sync,
+        borrow_check,
+        validate,
+        handles,
+        type_name,
+        is_empty,
+        same_type,
+        clone,
+        dtype,
+        deprecated,
+        since,
     } = args;
 
     if name.is_empty() {
@@ -78,65 +142,797 @@ pub(crate) fn c_wrapper_impl(args: TokenStream, item: TokenStream) -> TokenStrea
         .into();
     }
 
+    if sync.is_some() && borrow_check {
+        return syn::Error::new(
+            ident.span(),
+            "`borrow_check` cannot be combined with `sync`.",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let table = match handles.as_deref() {
+        None => false,
+        Some("table") => true,
+        Some(_) => {
+            return syn::Error::new(ident.span(), "`handles` must be \"table\".")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    if table && refcounted {
+        return syn::Error::new(
+            ident.span(),
+            "`handles = \"table\"` cannot be combined with `refcounted`.",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // Thread-safe wrappers store the object behind a lock and wrappers with `borrow_check`
+    // store it in a `BorrowCell`. `read` and `write` return guards that hold the lock or
+    // borrow, so the wrapped object is only accessed while it is locked or borrowed.
+    let object = if sync.is_some() || borrow_check {
+        quote! { Box<dyn c_api_tools::WrappedObject + Send + Sync> }
+    } else {
+        quote! { Box<dyn c_api_tools::WrappedObject> }
+    };
+    let poisoned = quote! { unwrap_or_else(std::sync::PoisonError::into_inner) };
+    let (storage, new_storage, try_read, try_write, try_lock, shared_reads) =
+        match (sync.as_deref(), borrow_check) {
+            (None, false) => (
+                quote! { #object },
+                quote! { Box::new(obj) },
+                quote! { Ok(&*self._ptr) },
+                quote! { Ok(&mut *self._ptr) },
+                quote! { Ok(&mut self._ptr) },
+                true,
+            ),
+            (None, true) => (
+                quote! { c_api_tools::BorrowCell<#object> },
+                quote! { c_api_tools::BorrowCell::new(Box::new(obj)) },
+                quote! { self._ptr.try_borrow().map(c_api_tools::ObjectGuard::new) },
+                quote! { self._ptr.try_borrow_mut().map(c_api_tools::ObjectGuard::new) },
+                quote! { self._ptr.try_borrow_mut() },
+                true,
+            ),
+            (Some("rwlock"), _) => (
+                quote! { std::sync::RwLock<#object> },
+                quote! { std::sync::RwLock::new(Box::new(obj)) },
+                quote! { Ok(c_api_tools::ObjectGuard::new(self._ptr.read().#poisoned)) },
+                quote! { Ok(c_api_tools::ObjectGuard::new(self._ptr.write().#poisoned)) },
+                quote! { Ok(self._ptr.write().#poisoned) },
+                // A thread that locks a `RwLock` for reading twice can deadlock.
+                false,
+            ),
+            (Some("mutex"), _) => (
+                quote! { std::sync::Mutex<#object> },
+                quote! { std::sync::Mutex::new(Box::new(obj)) },
+                quote! { Ok(c_api_tools::ObjectGuard::new(self._ptr.lock().#poisoned)) },
+                quote! { Ok(c_api_tools::ObjectGuard::new(self._ptr.lock().#poisoned)) },
+                quote! { Ok(self._ptr.lock().#poisoned) },
+                false,
+            ),
+            (Some(_), _) => {
+                return syn::Error::new(ident.span(), "`sync` must be \"rwlock\" or \"mutex\".")
+                    .to_compile_error()
+                    .into();
+            }
+        };
+    let thread_safe = sync.is_some() || borrow_check;
+    // Wrappers that can be shared between threads only keep dependencies that can be shared
+    // as well.
+    let dependency = if thread_safe {
+        quote! { std::sync::Arc<dyn std::any::Any + Send + Sync> }
+    } else {
+        quote! { std::sync::Arc<dyn std::any::Any> }
+    };
+    let bounds = if sync.is_some() || borrow_check {
+        quote! { std::any::Any + Send + Sync }
+    } else {
+        quote! { std::any::Any }
+    };
+    let (inner, inner_mut, write_receiver) = match (sync.is_some(), borrow_check) {
+        (false, false) => (
+            quote! {
+                /// Return reference to wrapped pointer.
+                pub fn inner(&self) -> &#object {
+                    &self._ptr
+                }
+            },
+            quote! { &mut self._ptr },
+            quote! { &mut self },
+        ),
+        (false, true) => (quote! {}, quote! { self._ptr.get_mut() }, quote! { &self }),
+        (true, _) => (
+            quote! {},
+            quote! { self._ptr.get_mut().#poisoned },
+            quote! { &self },
+        ),
+    };
+    let (can_borrow, can_borrow_mut) = if borrow_check {
+        (
+            quote! { !self._ptr.is_borrowed_mut() },
+            quote! { !self._ptr.is_borrowed() },
+        )
+    } else {
+        (quote! { true }, quote! { true })
+    };
+
+    // Validated wrappers start with a tag that identifies live wrappers of this type.
+    let (repr, tag_field, tag_init, valid_handle) = if validate {
+        (
+            quote! { #[repr(C)] },
+            quote! { _tag: c_api_tools::HandleTag, },
+            quote! { _tag: c_api_tools::HandleTag::new(c_api_tools::type_magic(#name)), },
+            quote! {
+                c_api_tools::HandleTag::check(
+                    ptr as *const c_api_tools::HandleTag,
+                    c_api_tools::type_magic(#name),
+                )
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {}, quote! { !ptr.is_null() })
+    };
+    // Creates statements that set an error and return `value` if `ptr` is not a live
+    // wrapper. They are empty unless the wrapper is validated.
+    let check_handle = |ptr: proc_macro2::TokenStream, value: proc_macro2::TokenStream| {
+        if validate {
+            quote! {
+                if !#ident::is_valid_handle(#ptr) {
+                    c_api_tools::set_last_error("Invalid or freed handle.");
+                    return #value;
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
+
+    // Wrappers with `handles = "table"` are passed to C as integer handles into a table of
+    // pointers. The generated functions look up the pointer of a handle before they use it.
+    let (ptr_mut, ptr_const, into_c, null_c) = if table {
+        (
+            quote! { u64 },
+            quote! { u64 },
+            quote! { into_handle },
+            quote! { 0 },
+        )
+    } else {
+        (
+            quote! { *mut #ident },
+            quote! { *const #ident },
+            quote! { into_raw },
+            quote! { std::ptr::null_mut() },
+        )
+    };
+    // Creates statements that pin the handle `ptr` for the rest of the function and replace
+    // it with its pointer, which is null for the handle 0. If the handle is not in the table,
+    // an error is set and `value` is returned.
+    let resolve_handle = |ptr: proc_macro2::TokenStream, value: proc_macro2::TokenStream| {
+        if table {
+            let pin = syn::Ident::new(&format!("{}_pin", ptr), Span::call_site());
+            quote! {
+                let #pin = #ident::pin_handle(#ptr);
+                let #ptr = match &#pin {
+                    Some(pin) => pin.get(),
+                    None if #ptr == 0 => std::ptr::null_mut(),
+                    None => {
+                        c_api_tools::set_last_error("Invalid or freed handle.");
+                        return #value;
+                    }
+                };
+            }
+        } else {
+            quote! {}
+        }
+    };
+
+    // The struct itself is not deprecated as the generated code refers to it.
+    let version_attributes = create_version_attributes(&deprecated, &since);
+    let struct_version_attributes = create_version_attributes(&None, &since);
+
+    // Refcounted wrappers are stored in an `Arc`. The pointers passed to C point to the
+    // wrapper inside the `Arc` allocation, so they can be used like pointers to boxed wrappers.
+    let (into_raw, free_raw) = if refcounted {
+        (
+            quote! { std::sync::Arc::into_raw(std::sync::Arc::new(self)) as *mut Self },
+            quote! { std::sync::Arc::decrement_strong_count(ptr) },
+        )
+    } else {
+        (
+            quote! { Box::into_raw(Box::new(self)) },
+            quote! { drop(Box::from_raw(ptr)) },
+        )
+    };
+
     let mut output = quote! {
         #(#attrs)*
+        #struct_version_attributes
+        #repr
         #vis struct #ident {
-            _ptr: Box<dyn std::any::Any>,
+            #tag_field
+            _ptr: #storage,
+            _dependencies: std::sync::Mutex<Vec<#dependency>>,
         }
 
         impl #ident {
-            /// Return reference to wrapped pointer.
-            pub fn inner(&self) -> &Box<dyn std::any::Any> {
-                &self._ptr
+            /// Create a wrapper for an object.
+            pub fn new<T: #bounds>(obj: T) -> Self {
+                Self {
+                    #tag_init
+                    _ptr: #new_storage,
+                    _dependencies: std::sync::Mutex::new(vec![]),
+                }
             }
+            #inner
             /// Return mutable reference to wrapped pointer.
-            pub fn inner_mut(&mut self) -> &mut Box<dyn std::any::Any> {
-                &mut self._ptr
+            pub fn inner_mut(&mut self) -> &mut #object {
+                #inner_mut
+            }
+            /// Return the wrapped object. Thread-safe wrappers stay locked until the returned
+            /// guard is dropped. Fails if the wrapper tracks borrows and is borrowed mutably.
+            pub fn try_read(&self) -> Result<impl std::ops::Deref<Target = dyn c_api_tools::WrappedObject> + '_, c_api_tools::BorrowError> {
+                #try_read
+            }
+            /// Return the wrapped object for modification. Thread-safe wrappers stay locked
+            /// until the returned guard is dropped. Fails if the wrapper tracks borrows and is
+            /// borrowed.
+            pub fn try_write(#write_receiver) -> Result<impl std::ops::DerefMut<Target = dyn c_api_tools::WrappedObject> + '_, c_api_tools::BorrowError> {
+                #try_write
+            }
+            /// Return the box that holds the wrapped object, so that the object can be moved out
+            /// of it. Thread-safe wrappers stay locked until the returned guard is dropped. Fails
+            /// if the wrapper tracks borrows and is borrowed.
+            pub fn try_lock(#write_receiver) -> Result<impl std::ops::DerefMut<Target = #object> + '_, c_api_tools::BorrowError> {
+                #try_lock
+            }
+            /// Move the wrapped object out of the wrapper if it has type `T`. The wrapper is
+            /// left empty. Fails if the wrapper tracks borrows and is borrowed.
+            pub fn try_take<T: std::any::Any>(#write_receiver) -> Result<Option<T>, c_api_tools::BorrowError> {
+                let mut object = self.try_lock()?;
+                let value = std::mem::replace(&mut *object, Box::new(()));
+                match value.downcast::<T>() {
+                    Ok(value) => Ok(Some(*value)),
+                    Err(value) => {
+                        *object = value;
+                        Ok(None)
+                    }
+                }
+            }
+            /// Return the wrapped object.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed mutably.
+            pub fn read(&self) -> impl std::ops::Deref<Target = dyn c_api_tools::WrappedObject> + '_ {
+                self.try_read().expect("wrapper already borrowed")
+            }
+            /// Return the wrapped object for modification.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed.
+            pub fn write(#write_receiver) -> impl std::ops::DerefMut<Target = dyn c_api_tools::WrappedObject> + '_ {
+                self.try_write().expect("wrapper already borrowed")
+            }
+            /// Move the wrapped object out of the wrapper if it has type `T`.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed.
+            pub fn take<T: std::any::Any>(#write_receiver) -> Option<T> {
+                self.try_take().expect("wrapper already borrowed")
+            }
+            /// Return true if the wrapped object can be borrowed. This is always true unless the
+            /// wrapper tracks borrows.
+            pub fn can_borrow(&self) -> bool {
+                #can_borrow
+            }
+            /// Return true if the wrapped object can be borrowed mutably. This is always true
+            /// unless the wrapper tracks borrows.
+            pub fn can_borrow_mut(&self) -> bool {
+                #can_borrow_mut
+            }
+            /// Return true if the object can be read through several references at the same
+            /// time on one thread. This is false for wrappers that are locked by a mutex or a
+            /// read-write lock.
+            pub const fn shared_reads() -> bool {
+                #shared_reads
+            }
+            /// Return true if the wrapper can be accessed from several threads at the same
+            /// time. This is true for wrappers with `sync` or `borrow_check`.
+            pub const fn thread_safe() -> bool {
+                #thread_safe
+            }
+            /// Return true if the wrapper is passed to C as an integer handle. This is true
+            /// for wrappers with `handles = "table"`.
+            pub const fn uses_handles() -> bool {
+                #table
+            }
+            /// Return true if the wrapped object has type `T`. Returns false if the wrapper
+            /// tracks borrows and is borrowed mutably.
+            pub fn is<T: std::any::Any>(&self) -> bool {
+                self.try_read().is_ok_and(|object| object.is::<T>())
+            }
+            /// Return the type id of the wrapped object. Fails if the wrapper tracks borrows
+            /// and is borrowed mutably.
+            pub fn try_object_type_id(&self) -> Result<std::any::TypeId, c_api_tools::BorrowError> {
+                self.try_read().map(|object| (*object).type_id())
+            }
+            /// Return the name of the type of the wrapped object. Fails if the wrapper tracks
+            /// borrows and is borrowed mutably.
+            pub fn try_type_name(&self) -> Result<&'static str, c_api_tools::BorrowError> {
+                self.try_read().map(|object| (*object).type_name())
+            }
+            /// Return true if the wrapper does not hold an object. Fails if the wrapper tracks
+            /// borrows and is borrowed mutably.
+            pub fn try_is_empty(&self) -> Result<bool, c_api_tools::BorrowError> {
+                self.try_read().map(|object| object.is_empty())
+            }
+            /// Return the type id of the wrapped object.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed mutably.
+            pub fn object_type_id(&self) -> std::any::TypeId {
+                self.try_object_type_id().expect("wrapper already borrowed")
+            }
+            /// Return the name of the type of the wrapped object.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed mutably.
+            pub fn type_name(&self) -> &'static str {
+                self.try_type_name().expect("wrapper already borrowed")
+            }
+            /// Return true if the wrapper does not hold an object.
+            ///
+            /// # Panics
+            /// Panics if the wrapper tracks borrows and is borrowed mutably.
+            pub fn is_empty(&self) -> bool {
+                self.try_is_empty().expect("wrapper already borrowed")
+            }
+            /// Keep an object alive until the wrapper is dropped.
+            pub fn attach_dependency(&self, dependency: #dependency) {
+                self._dependencies.lock().#poisoned.push(dependency);
+            }
+            /// Return the objects that are kept alive by the wrapper.
+            pub fn dependencies(&self) -> Vec<#dependency> {
+                self._dependencies.lock().#poisoned.clone()
+            }
+            /// Return false if `ptr` is null. If the wrapper validates handles, also return false
+            /// if `ptr` does not point to a live wrapper of this type.
+            ///
+            /// # Safety
+            /// `ptr` must be null or point to a wrapper. For validated wrappers, it can also
+            /// point to a wrapper of another type or to a freed wrapper.
+            pub unsafe fn is_valid_handle(ptr: *const Self) -> bool {
+                #valid_handle
+            }
+            /// Move the wrapper to the heap and return a pointer that can be passed to C.
+            pub fn into_raw(self) -> *mut Self {
+                #into_raw
+            }
+            /// Free a pointer returned by `into_raw`.
+            ///
+            /// # Safety
+            /// The pointer must have been returned by `into_raw` and must not be used afterwards.
+            pub unsafe fn free_raw(ptr: *mut Self) {
+                #free_raw
             }
         }
     };
 
+    if table {
+        output.extend(quote! {
+            impl #ident {
+                fn handles() -> &'static c_api_tools::HandleTable<#ident> {
+                    static HANDLES: c_api_tools::HandleTable<#ident> =
+                        c_api_tools::HandleTable::new(#ident::free_raw);
+                    &HANDLES
+                }
+                /// Move the wrapper to the heap and return a handle that can be passed to C.
+                pub fn into_handle(self) -> u64 {
+                    Self::handles().insert(self.into_raw())
+                }
+                /// Pin the wrapper of a handle, or return `None` if the handle is invalid or was
+                /// freed. The wrapper is not freed while the pin exists, even by another thread.
+                pub fn pin_handle(handle: u64) -> Option<c_api_tools::HandlePin<'static, Self>> {
+                    Self::handles().pin(handle)
+                }
+                /// Invalidate a handle and free its wrapper once it is no longer pinned.
+                /// Returns false if the handle is invalid or was freed.
+                pub fn free_handle(handle: u64) -> bool {
+                    Self::handles().remove(handle)
+                }
+            }
+        });
+    }
+
     if create {
         let name = syn::Ident::new((name.clone() + "_create").as_str(), Span::call_site());
 
         output.extend(quote! {
             /// Create a new instance of the wrapper.
+            #version_attributes
             #[no_mangle]
-            pub extern "C" fn #name() -> *mut #ident {
-                let obj = #ident { _ptr: Box::new(()) };
-                let ptr = Box::into_raw(Box::new(obj));
-                ptr
+            pub extern "C" fn #name() -> #ptr_mut {
+                #ident::new(()).#into_c()
             }
         });
     }
 
     if free {
         let name = syn::Ident::new((name.clone() + "_free").as_str(), Span::call_site());
+        let check = check_handle(quote! { ptr }, quote! {});
+
+        output.extend(if table {
+            quote! {
+                /// Free the instance of the wrapper. The handle is invalid afterwards.
+                #version_attributes
+                #[no_mangle]
+                pub extern "C" fn #name(ptr: u64) {
+                    if ptr != 0 && !#ident::free_handle(ptr) {
+                        c_api_tools::set_last_error("Invalid or freed handle.");
+                    }
+                }
+            }
+        } else {
+            quote! {
+                /// Free the instance of the wrapper.
+                #version_attributes
+                #[no_mangle]
+                pub unsafe extern "C" fn #name(ptr: #ptr_mut) {
+                    if ptr.is_null() {
+                        return;
+                    }
+                    #check
+                    unsafe {
+                        #ident::free_raw(ptr);
+                    }
+                }
+            }
+        });
+    }
+
+    if refcounted {
+        let retain = syn::Ident::new((name.clone() + "_retain").as_str(), Span::call_site());
+        let release = syn::Ident::new((name.clone() + "_release").as_str(), Span::call_site());
+        let ref_count = syn::Ident::new((name.clone() + "_ref_count").as_str(), Span::call_site());
+        let check_retain = check_handle(quote! { ptr }, quote! { std::ptr::null_mut() });
+        let check_release = check_handle(quote! { ptr }, quote! {});
+        let check_ref_count = check_handle(quote! { ptr }, quote! { 0 });
 
         output.extend(quote! {
-            /// Free the instance of the wrapper.
+            /// Add an owner to the wrapper and return the pointer.
+            ///
+            /// Every owner must call the release function once the wrapper is no longer needed.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #retain(ptr: *mut #ident) -> *mut #ident {
+                if ptr.is_null() {
+                    return ptr;
+                }
+                #check_retain
+                std::sync::Arc::increment_strong_count(ptr);
+                ptr
+            }
+
+            /// Remove an owner from the wrapper. The wrapper is freed when it has no owners.
+            #version_attributes
             #[no_mangle]
-            pub unsafe extern "C" fn #name(ptr: *mut #ident) {
+            pub unsafe extern "C" fn #release(ptr: *mut #ident) {
                 if ptr.is_null() {
                     return;
                 }
-                unsafe {
-                    drop(Box::from_raw(ptr));
+                #check_release
+                #ident::free_raw(ptr);
+            }
+
+            /// Return the number of owners of the wrapper, or 0 if the pointer is null.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #ref_count(ptr: *const #ident) -> usize {
+                if ptr.is_null() {
+                    return 0;
+                }
+                #check_ref_count
+                let wrapper = std::mem::ManuallyDrop::new(std::sync::Arc::from_raw(ptr));
+                std::sync::Arc::strong_count(&wrapper)
+            }
+        });
+    }
+
+    if refcounted {
+        output.extend(quote! {
+            impl c_api_tools::RefCounted for #ident {
+                unsafe fn share(ptr: *const Self) -> std::sync::Arc<Self> {
+                    std::sync::Arc::increment_strong_count(ptr);
+                    std::sync::Arc::from_raw(ptr)
                 }
             }
         });
     }
 
-    if unwrap {
+    for parent in &depends_on {
+        let parent = parent.parse::<syn::Ident>().unwrap();
+        let c_name = syn::Ident::new(
+            format!("{}_attach_{}", name, to_snake_case(&parent.to_string())).as_str(),
+            Span::call_site(),
+        );
+        let doc = format!(" Make the wrapper depend on a `{}`.", parent);
+        let lookup = resolve_handle(quote! { ptr }, quote! { false });
+        let check = check_handle(quote! { ptr }, quote! { false });
+
+        output.extend(quote! {
+            #[doc = #doc]
+            ///
+            /// The parent must be `refcounted`. It is kept alive until the wrapper is freed, even
+            /// if the parent is freed first. Returns false and sets an error if one of the
+            /// pointers is null.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #c_name(ptr: #ptr_mut, parent: *mut #parent) -> bool {
+                #lookup
+                if ptr.is_null() || parent.is_null() {
+                    c_api_tools::set_last_error("Cannot attach a dependency to a null pointer.");
+                    return false;
+                }
+                #check
+                if !#parent::is_valid_handle(parent) {
+                    c_api_tools::set_last_error("Invalid or freed handle.");
+                    return false;
+                }
+                (*ptr).attach_dependency(<#parent as c_api_tools::RefCounted>::share(parent));
+                true
+            }
+        });
+    }
+
+    if type_name {
+        let name = syn::Ident::new((name.clone() + "_type_name").as_str(), Span::call_site());
+        let lookup = resolve_handle(quote! { ptr }, quote! { std::ptr::null() });
+        let check = check_handle(quote! { ptr }, quote! { std::ptr::null() });
+
+        output.extend(quote! {
+            /// Return the name of the type of the wrapped object.
+            ///
+            /// The returned string is valid until the end of the program. Returns a null
+            /// pointer if the pointer is null. If the wrapper is borrowed mutably, an error is
+            /// set and a null pointer is returned.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #name(ptr: #ptr_const) -> *const std::ffi::c_char {
+                #lookup
+                if ptr.is_null() {
+                    return std::ptr::null();
+                }
+                #check
+                match (*ptr).try_type_name() {
+                    Ok(name) => c_api_tools::static_c_str(name),
+                    Err(error) => {
+                        c_api_tools::set_last_error(&format!("Cannot read the wrapper: {}.", error));
+                        std::ptr::null()
+                    }
+                }
+            }
+        });
+    }
+
+    if is_empty {
+        let name = syn::Ident::new((name.clone() + "_is_empty").as_str(), Span::call_site());
+        let lookup = resolve_handle(quote! { ptr }, quote! { true });
+        let check = check_handle(quote! { ptr }, quote! { true });
+
+        output.extend(quote! {
+            /// Return true if the wrapper does not hold an object or the pointer is null.
+            ///
+            /// If the wrapper is borrowed mutably, an error is set and true is returned.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #name(ptr: #ptr_const) -> bool {
+                #lookup
+                if ptr.is_null() {
+                    return true;
+                }
+                #check
+                match (*ptr).try_is_empty() {
+                    Ok(empty) => empty,
+                    Err(error) => {
+                        c_api_tools::set_last_error(&format!("Cannot read the wrapper: {}.", error));
+                        true
+                    }
+                }
+            }
+        });
+    }
+
+    if same_type {
+        let name = syn::Ident::new((name.clone() + "_same_type").as_str(), Span::call_site());
+        let lookup_a = resolve_handle(quote! { a }, quote! { false });
+        let lookup_b = resolve_handle(quote! { b }, quote! { false });
+        let check_a = check_handle(quote! { a }, quote! { false });
+        let check_b = check_handle(quote! { b }, quote! { false });
+
+        output.extend(quote! {
+            /// Return true if both wrappers hold objects of the same type.
+            ///
+            /// Returns false if one of the pointers is null. If one of the wrappers is borrowed
+            /// mutably, an error is set and false is returned.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #name(a: #ptr_const, b: #ptr_const) -> bool {
+                #lookup_a
+                #lookup_b
+                if a.is_null() || b.is_null() {
+                    return false;
+                }
+                #check_a
+                #check_b
+                match ((*a).try_object_type_id(), (*b).try_object_type_id()) {
+                    (Ok(a), Ok(b)) => a == b,
+                    (Err(error), _) | (_, Err(error)) => {
+                        c_api_tools::set_last_error(&format!("Cannot read the wrapper: {}.", error));
+                        false
+                    }
+                }
+            }
+        });
+    }
+
+    if !clone.is_empty() {
+        let c_name = syn::Ident::new((name.clone() + "_clone").as_str(), Span::call_site());
+        let lookup = resolve_handle(quote! { ptr }, quote! { #null_c });
+        let check = check_handle(quote! { ptr }, quote! { #null_c });
+        let types = clone
+            .iter()
+            .map(|ty| ty.value().parse::<proc_macro2::TokenStream>().unwrap());
+
+        output.extend(quote! {
+            impl #ident {
+                /// Return a wrapper that holds a clone of the wrapped object.
+                ///
+                /// Returns `None` if the object does not have one of the types listed in the
+                /// `clone` option of the wrapper or if the wrapper tracks borrows and is
+                /// borrowed mutably. Empty wrappers can always be cloned. The clone depends on
+                /// the same objects as the wrapper.
+                pub fn try_clone(&self) -> Option<Self> {
+                    let object = self.try_read().ok()?;
+                    let mut clone = if object.is_empty() {
+                        Self::new(())
+                    }
+                    #(
+                        else if let Some(obj) = object.downcast_ref::<#types>() {
+                            Self::new(obj.clone())
+                        }
+                    )*
+                    else {
+                        return None;
+                    };
+                    clone._dependencies = std::sync::Mutex::new(self.dependencies());
+                    Some(clone)
+                }
+            }
+
+            /// Create a new wrapper that holds a clone of the wrapped object.
+            ///
+            /// If the object cannot be cloned, an error is set and a null pointer is returned.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #c_name(ptr: #ptr_const) -> #ptr_mut {
+                #lookup
+                if ptr.is_null() {
+                    c_api_tools::set_last_error("Cannot clone a null pointer.");
+                    return #null_c;
+                }
+                #check
+                match (*ptr).try_clone() {
+                    Some(obj) => obj.#into_c(),
+                    None => {
+                        match (*ptr).try_type_name() {
+                            Ok(name) => c_api_tools::set_last_error(&format!(
+                                "Objects of type `{}` cannot be cloned.",
+                                name
+                            )),
+                            Err(error) => c_api_tools::set_last_error(&format!(
+                                "Cannot read the wrapper: {}.",
+                                error
+                            )),
+                        }
+                        #null_c
+                    }
+                }
+            }
+        });
+    }
+
+    if !dtype.is_empty() {
+        let c_name = syn::Ident::new((name.clone() + "_dtype").as_str(), Span::call_site());
+        let lookup = resolve_handle(quote! { ptr }, quote! { c_api_tools::DType::Undefined });
+        let check = check_handle(quote! { ptr }, quote! { c_api_tools::DType::Undefined });
+        let types = dtype
+            .iter()
+            .map(|ty| ty.value().parse::<proc_macro2::TokenStream>().unwrap());
+
+        output.extend(quote! {
+            impl #ident {
+                /// Return the dtype of the wrapped object.
+                ///
+                /// Returns `DType::Undefined` if the object does not have one of the types
+                /// listed in the `dtype` option of the wrapper. Fails if the wrapper tracks
+                /// borrows and is borrowed mutably.
+                pub fn try_dtype(&self) -> Result<c_api_tools::DType, c_api_tools::BorrowError> {
+                    let object = self.try_read()?;
+                    #(
+                        if let Some(obj) = object.downcast_ref::<#types>() {
+                            return Ok(c_api_tools::HasDType::dtype(obj));
+                        }
+                    )*
+                    Ok(c_api_tools::DType::Undefined)
+                }
+                /// Return the dtype of the wrapped object.
+                ///
+                /// Returns `DType::Undefined` if the object does not have one of the types
+                /// listed in the `dtype` option of the wrapper.
+                ///
+                /// # Panics
+                /// Panics if the wrapper tracks borrows and is borrowed mutably.
+                pub fn dtype(&self) -> c_api_tools::DType {
+                    self.try_dtype().expect("wrapper already borrowed")
+                }
+            }
+
+            /// Return the dtype of the wrapped object.
+            ///
+            /// Returns `Undefined` if the pointer is null or the wrapped object has no dtype.
+            /// If the wrapper is borrowed mutably, an error is set and `Undefined` is returned.
+            #version_attributes
+            #[no_mangle]
+            pub unsafe extern "C" fn #c_name(ptr: #ptr_const) -> c_api_tools::DType {
+                #lookup
+                if ptr.is_null() {
+                    return c_api_tools::DType::Undefined;
+                }
+                #check
+                match (*ptr).try_dtype() {
+                    Ok(dtype) => dtype,
+                    Err(error) => {
+                        c_api_tools::set_last_error(&format!("Cannot read the wrapper: {}.", error));
+                        c_api_tools::DType::Undefined
+                    }
+                }
+            }
+        });
+    }
+
+    if unwrap && table {
+        let name = syn::Ident::new((name.clone() + "_unwrap").as_str(), Span::call_site());
+
+        // The handle stays pinned while the returned reference exists, so that the wrapper is
+        // not freed while it is in use.
+        output.extend(quote! {
+            /// Unwrap the instance of the wrapper. The wrapper is not freed before the returned
+            /// reference is dropped.
+            unsafe fn #name(ptr: u64) -> Option<c_api_tools::PinnedMut<'static, #ident, #object>> {
+                let Some(pin) = #ident::pin_handle(ptr) else {
+                    if ptr != 0 {
+                        c_api_tools::set_last_error("Invalid or freed handle.");
+                    }
+                    return None;
+                };
+                Some(pin.map_mut(|wrapper| wrapper.inner_mut()))
+            }
+        });
+    } else if unwrap {
         let name = syn::Ident::new((name.clone() + "_unwrap").as_str(), Span::call_site());
+        let check = check_handle(quote! { ptr }, quote! { None });
 
         output.extend(quote! {
             /// Unwrap the instance of the wrapper.
-            unsafe fn #name(ptr: *mut #ident) -> Option<&'static mut Box<dyn std::any::Any>> {
+            unsafe fn #name(ptr: #ptr_mut) -> Option<&'static mut #object> {
                 if ptr.is_null() {
                     return None;
                 }
-                Some(&mut (*ptr)._ptr)
+                #check
+                Some((*ptr).inner_mut())
             }
         });
     }
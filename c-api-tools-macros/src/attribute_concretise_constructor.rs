@@ -0,0 +1,171 @@
+use crate::attribute_concretise_types::{
+    create_cfg_attribute, create_generic_arguments, get_function_arg_ident, ReplaceWithList,
+};
+use darling::Error;
+use darling::{ast::NestedMeta, FromMeta};
+use itertools::Itertools;
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Ident};
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+struct GenType {
+    name: String,
+    arg: usize,
+    replace_with: ReplaceWithList,
+}
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+struct ConcretiseConstructorArgs {
+    wrapper: String,
+    #[darling(multiple)]
+    gen_type: Vec<GenType>,
+    // The wrapper is returned to C as an integer handle. The wrapper must use
+    // `handles = "table"`.
+    handle: bool,
+}
+
+pub(crate) fn concretise_constructor_impl(args: TokenStream, item: TokenStream) -> TokenStream {
+    let syn::ItemFn {
+        vis,
+        sig,
+        block,
+        attrs,
+        ..
+    } = parse_macro_input!(item as syn::ItemFn);
+
+    let attr_args = match NestedMeta::parse_meta_list(args.into()) {
+        Ok(v) => v,
+        Err(e) => {
+            return TokenStream::from(Error::from(e).write_errors());
+        }
+    };
+
+    let args = match ConcretiseConstructorArgs::from_list(&attr_args) {
+        Ok(v) => v,
+        Err(e) => {
+            return TokenStream::from(e.write_errors());
+        }
+    };
+
+    let ConcretiseConstructorArgs {
+        wrapper,
+        gen_type,
+        handle,
+    } = &args;
+
+    if wrapper.is_empty() {
+        return syn::Error::new(
+            sig.ident.span(),
+            "`wrapper` must be a non-empty `concretise_constructor` attribute.",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let message = if *handle {
+        format!(
+            "Constructors with `handle` require a wrapper with `handles = \"table\"`, but `{}` has none.",
+            wrapper
+        )
+    } else {
+        format!(
+            "`{}` uses `handles = \"table\"`, so its constructors must set `handle`.",
+            wrapper
+        )
+    };
+    let wrapper = Ident::new(wrapper, Span::call_site());
+    let (output_type, into_c, null_c) = if *handle {
+        (quote! { u64 }, quote! { into_handle }, quote! { 0 })
+    } else {
+        (
+            quote! { *mut #wrapper },
+            quote! { into_raw },
+            quote! { std::ptr::null_mut() },
+        )
+    };
+    let gen_keys = gen_type.iter().map(|x| x.name.clone()).collect_vec();
+
+    // The `DType` arguments that select the concrete types.
+    let dtype_idents = gen_type
+        .iter()
+        .map(|x| {
+            let arg = sig
+                .inputs
+                .get(x.arg)
+                .unwrap_or_else(|| panic!("Argument {} does not exist.", x.arg));
+            get_function_arg_ident(arg)
+        })
+        .collect_vec();
+
+    let ident = &sig.ident;
+    let arg_idents = sig.inputs.iter().map(get_function_arg_ident).collect_vec();
+
+    // C passes the dtypes as integers, which are only converted to `DType` if they are valid.
+    let mut inputs = sig.inputs.clone();
+    for x in gen_type.iter() {
+        let dtype_ident = get_function_arg_ident(&sig.inputs[x.arg]);
+        inputs[x.arg] = parse_quote! { #dtype_ident: u8 };
+    }
+    let raw_idents = dtype_idents
+        .iter()
+        .map(|ident| format_ident!("{}_raw", ident))
+        .collect_vec();
+
+    let mut branches = quote! {};
+
+    for concrete_types in gen_type
+        .iter()
+        .map(|x| x.replace_with.iter())
+        .multi_cartesian_product()
+    {
+        let cfg = create_cfg_attribute(concrete_types.iter().filter_map(|x| x.cfg.as_ref()));
+        let concrete_types = concrete_types.iter().map(|x| x.value.clone()).collect_vec();
+        let types = concrete_types
+            .iter()
+            .map(|x| x.parse::<proc_macro2::TokenStream>().unwrap())
+            .collect_vec();
+        let generic_args = create_generic_arguments(&sig, &gen_keys, &concrete_types);
+
+        branches = quote! {
+            #branches
+            #cfg
+            if #(#dtype_idents == Some(<#types as c_api_tools::DTypeIdentifier>::dtype()))&&* {
+                #(let #dtype_idents = <#types as c_api_tools::DTypeIdentifier>::dtype();)*
+                let obj = #ident #generic_args(#(#arg_idents),*);
+                return #wrapper::new(obj).#into_c();
+            }
+        };
+    }
+
+    let unsupported = format!("Unsupported dtypes for `{}`: ({{}}).", ident);
+
+    let output = quote! {
+        #( #attrs)*
+        #[no_mangle]
+        #vis extern "C" fn #ident(#inputs) -> #output_type {
+            #vis #sig
+            #block
+
+            const _: () = assert!(#wrapper::uses_handles() == #handle, #message);
+
+            #(
+                let #raw_idents = #dtype_idents;
+                let #dtype_idents = c_api_tools::DType::from_u8(#raw_idents);
+            )*
+
+            #branches
+
+            let dtypes = [#(
+                #dtype_idents.map_or_else(|| #raw_idents.to_string(), |dtype| format!("{:?}", dtype))
+            ),*];
+            c_api_tools::set_last_error(&format!(#unsupported, dtypes.join(", ")));
+            #null_c
+        }
+    };
+
+    output.into()
+}
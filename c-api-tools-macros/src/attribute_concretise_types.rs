@@ -1,13 +1,15 @@
+use crate::attribute_c_wrappers::create_version_attributes;
+use darling::util::Override;
 use darling::Error;
 use darling::{ast::NestedMeta, FromMeta};
 use itertools::{izip, Itertools};
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::punctuated::Punctuated;
 use syn::{
-    parse_macro_input, Expr, ExprCall, FnArg, LitStr, Pat, PatIdent, Path, Signature, Token, Type,
-    TypePath, TypePtr,
+    parse_macro_input, parse_quote, Expr, ExprCall, FnArg, LitStr, Pat, PatIdent, Path, Signature,
+    Token, Type, TypePath, TypePtr,
 };
 use syn::{Ident, PatType};
 
@@ -22,7 +24,7 @@ fn replace_templates_with_types(names: &[String], templates: &[String]) -> Vec<S
 
     let mut complete_types = templates.to_vec();
 
-    for index in 0..ntypes - 1 {
+    for index in 0..ntypes.saturating_sub(1) {
         let replace_type = complete_types[index].clone();
         for ty in complete_types[index + 1..ntypes].iter_mut() {
             *ty = ty.replace(&("{{".to_owned() + &names[index] + "}}"), &replace_type);
@@ -31,68 +33,836 @@ fn replace_templates_with_types(names: &[String], templates: &[String]) -> Vec<S
     complete_types
 }
 
-fn create_if_let_condition(
+// Creates a boolean expression that checks if all wrappers passed for the fields
+// hold objects of the given concrete types.
+fn create_type_condition(
     args: &ConcretiseTypeArgs,
     concrete_field_types: &[String],
     sig: &Signature,
-    expr_call: &ExprCall,
 ) -> proc_macro2::TokenStream {
-    let mut left_condition: String = if args.field.len() > 1 {
-        "if let (".to_string()
+    let mut conditions = Vec::new();
+
+    for (field, concrete_field_type) in izip!(args.field.iter(), concrete_field_types.iter()) {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+
+        conditions.push(create_field_condition(arg, concrete_field_type));
+    }
+
+    if conditions.is_empty() {
+        quote! { true }
     } else {
-        "if let ".to_string()
-    };
+        quote! { #(#conditions)&&* }
+    }
+}
 
-    let mut right_condition: String = if args.field.len() > 1 {
-        "(".to_string()
+// Creates a boolean expression that checks if the wrapper passed for a field holds an
+// object of the given concrete type.
+fn create_field_condition(arg: &FnArg, concrete_field_type: &str) -> proc_macro2::TokenStream {
+    let ident = get_function_arg_ident(arg);
+    let ty = concrete_field_type
+        .parse::<proc_macro2::TokenStream>()
+        .unwrap();
+
+    if get_field_kind(arg) == FieldKind::Slice {
+        let len_ident = get_slice_len_ident(ident);
+        quote! {
+            (0..#len_ident).all(|index| (*#ident.add(index)).as_ref().unwrap().is::<#ty>())
+        }
     } else {
-        "".to_string()
-    };
+        quote! {
+            #ident.as_ref().unwrap().is::<#ty>()
+        }
+    }
+}
 
-    let mut is_first = true;
-    for (field, concrete_field_type) in izip!(args.field.iter(), concrete_field_types.iter()) {
-        if !is_first {
-            left_condition += ", ";
-            right_condition += ", ";
+// Creates statements that are executed if no branch matches. They set an error that names
+// the type held by the first field with an unsupported type and the types that the field
+// supports, and return `error`. If every field holds a supported type on its own, the error
+// names the types held by all fields.
+fn create_mismatch_errors(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    branches: &[Branch],
+    error: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut checks = quote! {};
+    let mut arg_names = Vec::new();
+    let mut held_names = Vec::new();
+
+    for (index, field) in args.field.iter().enumerate() {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+        let ident = get_function_arg_ident(arg);
+
+        // The wrapper can be borrowed mutably by another thread since the borrow checks.
+        let borrowed_message = create_borrowed_message(ident);
+        let held_name = |wrapper: proc_macro2::TokenStream| {
+            quote! {
+                match #wrapper.try_type_name() {
+                    Ok(name) => name,
+                    Err(_) => {
+                        c_api_tools::set_last_error(#borrowed_message);
+                        return #error;
+                    }
+                }
+            }
+        };
+        let held = if get_field_kind(arg) == FieldKind::Slice {
+            let len_ident = get_slice_len_ident(ident);
+            let name = held_name(quote! { (**#ident) });
+            quote! {
+                if #len_ident == 0 { "()" } else { #name }
+            }
         } else {
-            is_first = false;
+            held_name(quote! { (*#ident) })
+        };
+
+        // Each type only needs to be checked once for every `cfg` attribute.
+        let mut entries = Vec::new();
+        for branch in branches.iter() {
+            let entry = (branch.field_types[index].clone(), branch.cfg.to_string());
+            if entries.contains(&entry) {
+                continue;
+            }
+            entries.push(entry);
+
+            let cfg = &branch.cfg;
+            let ty = branch.field_types[index]
+                .parse::<proc_macro2::TokenStream>()
+                .unwrap();
+            let condition = create_field_condition(arg, &branch.field_types[index]);
+            checks = quote! {
+                #checks
+                #cfg
+                {
+                    expected.push(std::any::type_name::<#ty>());
+                    found = found || #condition;
+                }
+            };
         }
 
+        let name = ident.to_string();
+        checks = quote! {
+            #checks
+            if !found {
+                c_api_tools::set_type_mismatch_error(#name, #held, &expected);
+                return #error;
+            }
+            expected.clear();
+            found = false;
+        };
+
+        arg_names.push(name);
+        held_names.push(held);
+    }
+
+    if args.field.is_empty() {
+        return quote! {
+            c_api_tools::set_last_error("Unsupported arguments.");
+            return #error;
+        };
+    }
+
+    quote! {
+        #[allow(unused_mut, unused_assignments)]
+        let mut expected = Vec::<&'static str>::new();
+        #[allow(unused_mut, unused_assignments)]
+        let mut found = false;
+        #checks
+        c_api_tools::set_type_combination_error(&[#((#arg_names, #held_names)),*]);
+        return #error;
+    }
+}
+
+// Creates statements that shadow the wrapper pointers of the fields with the wrapped
+// objects of the given concrete types. The statements must only be executed after the
+// condition from `create_type_condition` holds. The objects of thread-safe wrappers are
+// locked in the order of the addresses of the wrappers and stay locked until the function
+// returns. As another thread can replace an object before it is locked, an error is
+// returned if an object does not have the expected type.
+// If the target type of a field differs from the stored type, the object is converted
+// with `From` after it has been unwrapped.
+// Objects of owned fields are only moved out of their wrappers after all steps that can
+// fail, so that a failed call leaves every wrapper unchanged.
+fn create_bindings(
+    args: &ConcretiseTypeArgs,
+    concrete_field_types: &[String],
+    target_field_types: &[String],
+    sig: &Signature,
+) -> proc_macro2::TokenStream {
+    let mut bindings = quote! {};
+    let mut takes = quote! {};
+    let mut conversions = quote! {};
+    let mut lock_order = quote! {};
+    let mut guard_declarations = quote! {};
+    let mut lock_arms = quote! {};
+    let mut guard_patterns = Vec::new();
+    let mut guard_values = Vec::new();
+    let mut has_slice = false;
+
+    for (field_index, (field, concrete_field_type, target_field_type)) in izip!(
+        args.field.iter(),
+        concrete_field_types.iter(),
+        target_field_types.iter()
+    )
+    .enumerate()
+    {
         let arg = sig
             .inputs
             .get(field.arg)
             .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
 
         let ident = get_function_arg_ident(arg);
-        let mutability = function_arg_is_mutable(arg);
-
-        left_condition += &("Some(".to_owned() + &ident.to_string() + ")");
-        if mutability {
-            right_condition += &(ident.to_string()
-                + ".as_mut().unwrap().inner_mut().downcast_mut::<"
-                + concrete_field_type
-                + ">()");
+        let ty = concrete_field_type
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap();
+        let replaced_message = format!(
+            "The object held by `{}` was replaced by another thread.",
+            ident
+        );
+        let target = target_field_type
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap();
+        let kind = get_field_kind(arg);
+        let guard = format_ident!("{}_guard", ident);
+        let replaced = quote! {
+            {
+                c_api_tools::set_last_error(#replaced_message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
+        let borrowed_message = create_borrowed_message(ident);
+        let borrowed = quote! {
+            {
+                c_api_tools::set_last_error(#borrowed_message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
+
+        if concrete_field_type != target_field_type {
+            let conversion = match kind {
+                FieldKind::Ref => quote! {
+                    let #ident = <#target as From<&#ty>>::from(#ident);
+                    let #ident = &#ident;
+                },
+                FieldKind::RefMut => panic!("Mutable fields cannot be coerced."),
+                FieldKind::Owned => quote! {
+                    let #ident = <#target as From<#ty>>::from(#ident);
+                },
+                FieldKind::Slice => quote! {
+                    let #ident = #ident
+                        .iter()
+                        .map(|value| <#target as From<&#ty>>::from(value))
+                        .collect::<Vec<_>>();
+                    let #ident = #ident.iter().collect::<Vec<_>>();
+                    let #ident = &#ident[..];
+                },
+            };
+            conversions = quote! {
+                #conversions
+                #conversion
+            };
+        }
+
+        let field_index = proc_macro2::Literal::usize_unsuffixed(field_index);
+        let lock = match kind {
+            FieldKind::Ref | FieldKind::Slice => quote! { try_read },
+            FieldKind::RefMut => quote! { try_write },
+            FieldKind::Owned => quote! { try_lock },
+        };
+        if kind == FieldKind::Slice {
+            let len_ident = get_slice_len_ident(ident);
+            lock_order = quote! {
+                #lock_order
+                order.extend((0..#len_ident).map(|index| (*#ident.add(index) as *const (), #field_index, index)));
+            };
+            guard_declarations = quote! {
+                #guard_declarations
+                let mut #guard = (0..#len_ident).map(|_| None).collect::<Vec<_>>();
+            };
+            lock_arms = quote! {
+                #lock_arms
+                #field_index => {
+                    let Ok(guard) = (**#ident.add(index)).#lock() else #borrowed;
+                    #guard[index] = Some(guard);
+                }
+            };
+            guard_values.push(quote! {
+                #guard.into_iter().map(Option::unwrap).collect::<Vec<_>>()
+            });
+            has_slice = true;
         } else {
-            right_condition += &(ident.to_string()
-                + ".as_ref().unwrap().inner().downcast_ref::<"
-                + concrete_field_type
-                + ">()");
+            lock_order = quote! {
+                #lock_order
+                order.push((#ident as *const (), #field_index, 0));
+            };
+            guard_declarations = quote! {
+                #guard_declarations
+                let mut #guard = None;
+            };
+            lock_arms = quote! {
+                #lock_arms
+                #field_index => {
+                    let Ok(guard) = (*#ident).#lock() else #borrowed;
+                    #guard = Some(guard);
+                }
+            };
+            guard_values.push(quote! { #guard.unwrap() });
         }
+        guard_patterns.push(if matches!(kind, FieldKind::RefMut | FieldKind::Owned) {
+            quote! { mut #guard }
+        } else {
+            quote! { #guard }
+        });
+
+        let binding = match kind {
+            FieldKind::Ref => quote! {
+                let Some(#ident) = #guard.downcast_ref::<#ty>() else #replaced;
+            },
+            FieldKind::RefMut => quote! {
+                let Some(#ident) = #guard.downcast_mut::<#ty>() else #replaced;
+            },
+            FieldKind::Owned => {
+                // The wrapper stays locked until the object is moved out of it, which leaves
+                // the wrapper empty. If the field sets `free`, the wrapper is freed afterwards.
+                let free = if field.free {
+                    let wrapper = Ident::new(&field.wrapper, Span::call_site());
+                    if field.handle {
+                        let handle_ident = get_handle_ident(ident);
+                        quote! { #wrapper::free_handle(#handle_ident); }
+                    } else {
+                        quote! { #wrapper::free_raw(#ident); }
+                    }
+                } else {
+                    quote! {}
+                };
+                takes = quote! {
+                    #takes
+                    let #ident = {
+                        let object = std::mem::replace(&mut *#guard, Box::new(()));
+                        drop(#guard);
+                        #free
+                        object
+                    };
+                    let Ok(#ident) = #ident.downcast::<#ty>() else {
+                        unreachable!()
+                    };
+                    let #ident = *#ident;
+                };
+                quote! {
+                    if !(**#guard).is::<#ty>() #replaced
+                }
+            }
+            FieldKind::Slice => quote! {
+                let Some(#ident) = #guard
+                    .iter()
+                    .map(|object| object.downcast_ref::<#ty>())
+                    .collect::<Option<Vec<_>>>()
+                else #replaced;
+                let #ident = &#ident[..];
+            },
+        };
+
+        bindings = quote! {
+            #bindings
+            #binding
+        };
     }
 
-    if args.field.len() > 1 {
-        left_condition += ")";
-        right_condition += ")";
-    };
+    // All wrappers are locked in the order of their addresses, so that two calls that lock
+    // the same wrappers in a different argument order cannot deadlock.
+    if !guard_patterns.is_empty() {
+        let index = if has_slice {
+            quote! { index }
+        } else {
+            quote! { _ }
+        };
+        bindings = quote! {
+            let (#(#guard_patterns,)*) = {
+                let mut order = Vec::<(*const (), usize, usize)>::new();
+                #lock_order
+                order.sort_unstable();
+                #guard_declarations
+                for (_, field, #index) in order {
+                    match field {
+                        #lock_arms
+                        _ => unreachable!(),
+                    }
+                }
+                (#(#guard_values,)*)
+            };
+            #bindings
+        };
+    }
 
-    let condition = left_condition + " = " + &right_condition;
-    let condition = condition.parse::<proc_macro2::TokenStream>().unwrap();
+    // Scalars are converted to the type that the function is called with.
+    for scalar in args.scalar.iter() {
+        let ident = get_function_arg_ident(&sig.inputs[scalar.arg]);
+        let name = ident.to_string();
+        bindings = quote! {
+            #bindings
+            let #ident = match c_api_tools::CScalar::convert_argument(#ident, #name) {
+                Some(value) => value,
+                None => return c_api_tools::ErrorValue::error_value(),
+            };
+        };
+    }
 
     quote! {
-      #condition {
-          #expr_call
-      } else
+        #bindings
+        #takes
+        #conversions
+    }
+}
+
+// Creates statements that pin the integer handles of fields with `handle` for the rest of
+// the function and replace them by pointers to their wrappers. Handles that are not in the
+// table of the wrapper become null pointers.
+fn create_handle_resolution(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+) -> proc_macro2::TokenStream {
+    let mut resolution = quote! {};
+
+    for field in args.field.iter().filter(|field| field.handle) {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+        let ident = get_function_arg_ident(arg);
+        let wrapper = Ident::new(&field.wrapper, Span::call_site());
+        let handle_ident = get_handle_ident(ident);
+        let pin_ident = format_ident!("{}_pin", ident);
+
+        let statements = if get_field_kind(arg) == FieldKind::Slice {
+            let len_ident = get_slice_len_ident(ident);
+            quote! {
+                let #handle_ident = #ident;
+                let #pin_ident = if #handle_ident.is_null() {
+                    Vec::new()
+                } else {
+                    (0..#len_ident)
+                        .map(|index| #wrapper::pin_handle(*#handle_ident.add(index)))
+                        .collect::<Vec<_>>()
+                };
+                let #ident = #pin_ident
+                    .iter()
+                    .map(|pin| pin.as_ref().map_or(std::ptr::null(), |pin| pin.get() as *const #wrapper))
+                    .collect::<Vec<_>>();
+                let #ident = if #handle_ident.is_null() {
+                    std::ptr::null()
+                } else {
+                    #ident.as_ptr()
+                };
+            }
+        } else {
+            // The handle is kept to free it when the wrapper is consumed.
+            quote! {
+                let #handle_ident = #ident;
+                let #pin_ident = #wrapper::pin_handle(#handle_ident);
+                let #ident = #pin_ident.as_ref().map_or(std::ptr::null_mut(), |pin| pin.get());
+            }
+        };
+
+        resolution = quote! {
+            #resolution
+            #statements
+        };
+    }
+
+    resolution
+}
+
+// Creates compile time assertions that fields set `handle` if and only if their wrapper
+// uses `handles = "table"`.
+fn create_handle_assertions(args: &ConcretiseTypeArgs) -> proc_macro2::TokenStream {
+    let assertions = args.field.iter().map(|field| {
+        let wrapper = Ident::new(&field.wrapper, Span::call_site());
+        let handle = field.handle;
+        let message = if handle {
+            format!(
+                "Fields with `handle` require a wrapper with `handles = \"table\"`, but `{}` has none.",
+                field.wrapper
+            )
+        } else {
+            format!(
+                "`{}` uses `handles = \"table\"`, so its fields must set `handle`.",
+                field.wrapper
+            )
+        };
+        quote! {
+            const _: () = assert!(#wrapper::uses_handles() == #handle, #message);
+        }
+    });
+
+    quote! { #(#assertions)* }
+}
+
+// Creates for every field a boolean expression that is true if a pointer passed for the
+// field is null or, if the wrapper validates handles, does not point to a live wrapper.
+fn create_invalid_handle_conditions(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+) -> Vec<(Ident, proc_macro2::TokenStream)> {
+    let mut conditions = Vec::new();
+
+    for field in args.field.iter() {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+        let ident = get_function_arg_ident(arg);
+        let wrapper = Ident::new(&field.wrapper, Span::call_site());
+
+        let condition = if get_field_kind(arg) == FieldKind::Slice {
+            let len_ident = get_slice_len_ident(ident);
+            quote! {
+                #len_ident > 0
+                    && (#ident.is_null()
+                        || (0..#len_ident).any(|index| !#wrapper::is_valid_handle(*#ident.add(index))))
+            }
+        } else {
+            quote! { !#wrapper::is_valid_handle(#ident) }
+        };
+        conditions.push((ident.clone(), condition));
+    }
+
+    conditions
+}
+
+// Creates runtime checks that set an error for the first field with an invalid pointer.
+// They run before all other checks, which access the wrappers.
+fn create_handle_checks(args: &ConcretiseTypeArgs, sig: &Signature) -> proc_macro2::TokenStream {
+    let mut checks = quote! {};
+
+    for (ident, condition) in create_invalid_handle_conditions(args, sig) {
+        let message = format!("Argument `{}` is an invalid or freed handle.", ident);
+        checks = quote! {
+            #checks
+            if #condition {
+                c_api_tools::set_last_error(#message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
+    }
+
+    checks
+}
+
+// The error that is set if the wrapper of a field is already borrowed.
+fn create_borrowed_message(ident: &Ident) -> String {
+    format!(
+        "Argument `{}` cannot be borrowed: wrapper already borrowed.",
+        ident
+    )
+}
+
+// Creates runtime checks that reject wrappers that track borrows if they are already
+// borrowed in a way that conflicts with the field, for example by a call that has not
+// returned yet. The checks run before the types are checked, so that a conflicting call
+// fails with a borrow error instead of a type error.
+fn create_borrow_checks(args: &ConcretiseTypeArgs, sig: &Signature) -> proc_macro2::TokenStream {
+    let mut checks = quote! {};
+
+    for field in args.field.iter() {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+        let ident = get_function_arg_ident(arg);
+        let message = create_borrowed_message(ident);
+
+        let condition = match get_field_kind(arg) {
+            FieldKind::Ref => quote! { !(*#ident).can_borrow() },
+            FieldKind::RefMut | FieldKind::Owned => quote! { !(*#ident).can_borrow_mut() },
+            FieldKind::Slice => {
+                let len_ident = get_slice_len_ident(ident);
+                quote! {
+                    (0..#len_ident).any(|index| !(**#ident.add(index)).can_borrow())
+                }
+            }
+        };
+
+        checks = quote! {
+            #checks
+            if #condition {
+                c_api_tools::set_last_error(#message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
+    }
+
+    checks
+}
+
+// Creates runtime checks that reject wrapper pointers passed for more than one field
+// if any of the aliased fields is borrowed mutably or consumed. Otherwise the generated
+// code would create overlapping `&mut` and `&` references to the same object.
+fn create_alias_checks(args: &ConcretiseTypeArgs, sig: &Signature) -> proc_macro2::TokenStream {
+    let mut checks = quote! {};
+
+    for (index, first) in args.field.iter().enumerate() {
+        for second in args.field[index + 1..].iter() {
+            let first_arg = sig
+                .inputs
+                .get(first.arg)
+                .unwrap_or_else(|| panic!("Argument {} does not exist.", first.arg));
+            let second_arg = sig
+                .inputs
+                .get(second.arg)
+                .unwrap_or_else(|| panic!("Argument {} does not exist.", second.arg));
+
+            let first_kind = get_field_kind(first_arg);
+            let second_kind = get_field_kind(second_arg);
+
+            // Shared fields can only alias if the wrapper can be locked for reading twice.
+            let shared = first_kind.is_shared() && second_kind.is_shared();
+            let first_wrapper = Ident::new(&first.wrapper, Span::call_site());
+
+            let first_ident = get_function_arg_ident(first_arg);
+            let second_ident = get_function_arg_ident(second_arg);
+
+            // Slices alias another field if one of their elements points to its wrapper.
+            let condition = if first_kind == FieldKind::Slice && second_kind == FieldKind::Slice {
+                let first_len_ident = get_slice_len_ident(first_ident);
+                let second_len_ident = get_slice_len_ident(second_ident);
+                quote! {
+                    (0..#first_len_ident).any(|index| {
+                        (0..#second_len_ident).any(|other| {
+                            std::ptr::addr_eq(*#first_ident.add(index), *#second_ident.add(other))
+                        })
+                    })
+                }
+            } else if first_kind == FieldKind::Slice {
+                let len_ident = get_slice_len_ident(first_ident);
+                quote! {
+                    (0..#len_ident).any(|index| std::ptr::addr_eq(*#first_ident.add(index), #second_ident))
+                }
+            } else if second_kind == FieldKind::Slice {
+                let len_ident = get_slice_len_ident(second_ident);
+                quote! {
+                    (0..#len_ident).any(|index| std::ptr::addr_eq(*#second_ident.add(index), #first_ident))
+                }
+            } else {
+                quote! { std::ptr::addr_eq(#first_ident, #second_ident) }
+            };
+
+            let (condition, message) = if shared {
+                (
+                    quote! { !#first_wrapper::shared_reads() && #condition },
+                    format!(
+                        "Arguments `{}` and `{}` point to the same wrapper, which cannot be locked twice.",
+                        first_ident, second_ident
+                    ),
+                )
+            } else {
+                (
+                    condition,
+                    format!(
+                        "Arguments `{}` and `{}` point to the same wrapper, but at least one of them is mutable or consumed.",
+                        first_ident, second_ident
+                    ),
+                )
+            };
+
+            checks = quote! {
+                #checks
+                if #condition {
+                    c_api_tools::set_last_error(#message);
+                    return c_api_tools::ErrorValue::error_value();
+                }
+            };
+        }
+    }
+
+    checks
+}
+
+// Splits an entry `Enum::Variant => value` of a `gen_enum` into the pattern that matches
+// the variant and the type or constant that the variant is mapped to.
+fn parse_enum_entry(entry: &str) -> (proc_macro2::TokenStream, String) {
+    let (pattern, value) = entry.split_once("=>").unwrap_or_else(|| {
+        panic!(
+            "Enum entry `{}` must have the form `Enum::Variant => value`.",
+            entry
+        )
+    });
+    (
+        pattern.trim().parse::<proc_macro2::TokenStream>().unwrap(),
+        value.trim().to_string(),
+    )
+}
+
+// Creates statements that convert the integers passed from C for `gen_enum` arguments to
+// the enum. An integer that is not the discriminant of a mapped variant is never converted,
+// as that would be undefined behaviour. Instead `invalid` is called with the name of the
+// argument to create the statements that run in that case. They must return.
+fn create_enum_conversions(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    invalid: impl Fn(&Ident) -> proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut conversions = quote! {};
+
+    for gen_enum in args.gen_enum.iter() {
+        let FnArg::Typed(arg) = sig
+            .inputs
+            .get(gen_enum.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", gen_enum.arg))
+        else {
+            panic!("Argument must be typed.");
+        };
+        let ident = get_function_arg_ident(&FnArg::Typed(arg.clone())).clone();
+        let ty = &arg.ty;
+        let repr = gen_enum.repr();
+        let invalid = invalid(&ident);
+
+        let matches = gen_enum.replace_with.iter().map(|entry| {
+            let cfg = create_cfg_attribute(&entry.cfg);
+            let (pattern, _) = parse_enum_entry(&entry.value);
+            quote! {
+                #cfg
+                if #ident == #pattern as #repr {
+                    value = Some(#pattern);
+                }
+            }
+        });
+
+        conversions = quote! {
+            #conversions
+            let #ident = {
+                #[allow(unused_mut)]
+                let mut value: Option<#ty> = None;
+                #(#matches)*
+                match value {
+                    Some(value) => value,
+                    None => #invalid,
+                }
+            };
+        };
+    }
+
+    conversions
+}
+
+// Creates runtime checks that the values of enum and const arguments are mapped by a
+// `gen_enum` or `gen_const`. Enum arguments are converted from integers.
+fn create_value_checks(args: &ConcretiseTypeArgs, sig: &Signature) -> proc_macro2::TokenStream {
+    let mut checks = create_enum_conversions(args, sig, |ident| {
+        let message = format!("Unsupported value of `{}`.", ident);
+        quote! {
+            {
+                c_api_tools::set_last_error(#message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        }
+    });
+
+    for gen_value in args.gen_values().iter().filter(|x| !x.is_enum) {
+        let Some(arg) = gen_value.arg else {
+            continue;
+        };
+        let arg = sig
+            .inputs
+            .get(arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", arg));
+        let ident = get_function_arg_ident(arg);
+
+        let message = format!("Unsupported value of `{}`.", ident);
+
+        let condition = if gen_value.entries.iter().all(|entry| entry.cfg.is_none()) {
+            let patterns = gen_value.entries.iter().map(|entry| &entry.pattern);
+            quote! { matches!(#ident, #(#patterns)|*) }
+        } else {
+            // Patterns of conditional entries can only be matched in statements.
+            let matches = gen_value.entries.iter().map(|entry| {
+                let cfg = create_cfg_attribute(&entry.cfg);
+                let pattern = &entry.pattern;
+                quote! {
+                    #cfg
+                    if matches!(#ident, #pattern) {
+                        supported = true;
+                    }
+                }
+            });
+            quote! {
+                {
+                    #[allow(unused_mut)]
+                    let mut supported = false;
+                    #(#matches)*
+                    supported
+                }
+            }
+        };
+
+        checks = quote! {
+            #checks
+            if !#condition {
+                c_api_tools::set_last_error(#message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
     }
+
+    checks
+}
+
+// Creates runtime checks that all elements of a slice field hold objects of the same type.
+fn create_slice_checks(args: &ConcretiseTypeArgs, sig: &Signature) -> proc_macro2::TokenStream {
+    let mut checks = quote! {};
+
+    for field in args.field.iter() {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+
+        if get_field_kind(arg) != FieldKind::Slice {
+            continue;
+        }
+
+        let ident = get_function_arg_ident(arg);
+        let len_ident = get_slice_len_ident(ident);
+
+        let wrapper = Ident::new(&field.wrapper, Span::call_site());
+
+        let message = format!("Elements of `{}` hold objects of different types.", ident);
+        let borrowed_message = create_borrowed_message(ident);
+        let locked_message = format!(
+            "Elements of `{}` point to the same wrapper, which cannot be locked twice.",
+            ident
+        );
+
+        checks = quote! {
+            #checks
+            let Ok(type_ids) = (0..#len_ident)
+                .map(|index| (**#ident.add(index)).try_object_type_id())
+                .collect::<Result<Vec<_>, _>>()
+            else {
+                c_api_tools::set_last_error(#borrowed_message);
+                return c_api_tools::ErrorValue::error_value();
+            };
+            if type_ids.iter().any(|type_id| *type_id != type_ids[0]) {
+                c_api_tools::set_last_error(#message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+            if !#wrapper::shared_reads()
+                && (1..#len_ident).any(|index| {
+                    (0..index).any(|other| std::ptr::eq(*#ident.add(index), *#ident.add(other)))
+                })
+            {
+                c_api_tools::set_last_error(#locked_message);
+                return c_api_tools::ErrorValue::error_value();
+            }
+        };
+    }
+
+    checks
 }
 
 fn create_ptr_argument(var_name: &str, ptr_type: &str) -> PatType {
@@ -128,19 +898,50 @@ fn create_ptr_argument(var_name: &str, ptr_type: &str) -> PatType {
     }
 }
 
-fn function_arg_is_mutable(arg: &FnArg) -> bool {
+// The way in which a field argument is passed to the wrapped function.
+#[derive(PartialEq)]
+enum FieldKind {
+    // The argument is a shared reference `&T`.
+    Ref,
+    // The argument is a mutable reference `&mut T`.
+    RefMut,
+    // The argument is an owned value `T` that is moved out of the wrapper.
+    Owned,
+    // The argument is a slice of shared references `&[&T]`.
+    Slice,
+}
+
+impl FieldKind {
+    // Return true if the field only creates shared references to wrapped objects.
+    fn is_shared(&self) -> bool {
+        matches!(self, FieldKind::Ref | FieldKind::Slice)
+    }
+}
+
+fn get_field_kind(arg: &FnArg) -> FieldKind {
     if let FnArg::Typed(arg) = arg {
         if let Type::Reference(ty) = arg.ty.as_ref() {
-            ty.mutability.is_some()
+            if let Type::Slice(slice) = ty.elem.as_ref() {
+                if ty.mutability.is_some()
+                    || !matches!(slice.elem.as_ref(), Type::Reference(elem) if elem.mutability.is_none())
+                {
+                    panic!("Slice arguments must have the form `&[&T]`.");
+                }
+                FieldKind::Slice
+            } else if ty.mutability.is_some() {
+                FieldKind::RefMut
+            } else {
+                FieldKind::Ref
+            }
         } else {
-            panic!("Type to replace must be a reference type.")
+            FieldKind::Owned
         }
     } else {
         panic!("Argument must be typed.");
     }
 }
 
-fn get_function_arg_ident(arg: &FnArg) -> &Ident {
+pub(crate) fn get_function_arg_ident(arg: &FnArg) -> &Ident {
     if let FnArg::Typed(arg) = arg {
         if let Pat::Ident(pat_ident) = arg.pat.as_ref() {
             &pat_ident.ident
@@ -152,22 +953,82 @@ fn get_function_arg_ident(arg: &FnArg) -> &Ident {
     }
 }
 
+// Slice fields are passed from C as a pointer to the first element and a length.
+// This returns the name of the length argument.
+fn get_slice_len_ident(ident: &Ident) -> Ident {
+    format_ident!("{}_len", ident)
+}
+
+// Returns the name of the variable that holds the integer handle of a field with `handle`.
+fn get_handle_ident(ident: &Ident) -> Ident {
+    format_ident!("{}_handle", ident)
+}
+
 fn create_signature(args: &ConcretiseTypeArgs, old_signature: &Signature) -> Signature {
     let Signature {
         ident,
-        mut inputs,
+        inputs: old_inputs,
         output,
         ..
     } = old_signature.clone();
 
-    for field in args.field.iter() {
-        let arg = inputs
-            .get_mut(field.arg)
-            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+    for arg in args
+        .field
+        .iter()
+        .map(|field| field.arg)
+        .chain(args.scalar.iter().map(|scalar| scalar.arg))
+    {
+        if arg >= old_inputs.len() {
+            panic!("Argument {} does not exist.", arg);
+        }
+    }
 
-        let ident = get_function_arg_ident(arg);
+    let output = if args.return_scalar {
+        parse_quote! { -> c_api_tools::CScalar }
+    } else {
+        output
+    };
+
+    let mut inputs = Punctuated::<FnArg, Token![,]>::new();
+
+    for (index, arg) in old_inputs.into_iter().enumerate() {
+        if args.scalar.iter().any(|scalar| scalar.arg == index) {
+            let ident = get_function_arg_ident(&arg);
+            inputs.push(parse_quote! { #ident: c_api_tools::CScalar });
+            continue;
+        }
+
+        if let Some(gen_enum) = args.gen_enum.iter().find(|x| x.arg == index) {
+            let ident = get_function_arg_ident(&arg);
+            let repr = gen_enum.repr();
+            inputs.push(parse_quote! { #ident: #repr });
+            continue;
+        }
+
+        let Some(field) = args.field.iter().find(|field| field.arg == index) else {
+            inputs.push(arg);
+            continue;
+        };
 
-        *arg = FnArg::Typed(create_ptr_argument(&ident.to_string(), &field.wrapper));
+        let ident = get_function_arg_ident(&arg);
+
+        if get_field_kind(&arg) == FieldKind::Slice {
+            let wrapper = Ident::new(&field.wrapper, Span::call_site());
+            let len_ident = get_slice_len_ident(ident);
+            if field.handle {
+                inputs.push(parse_quote! { #ident: *const u64 });
+            } else {
+                inputs.push(parse_quote! { #ident: *const *const #wrapper });
+            }
+            inputs.push(parse_quote! { #len_ident: usize });
+        } else if field.handle {
+            inputs.push(parse_quote! { #ident: u64 });
+        } else {
+            inputs.push(FnArg::Typed(create_ptr_argument(
+                &ident.to_string(),
+                &field.wrapper,
+            )));
+        }
     }
 
     Signature {
@@ -189,7 +1050,699 @@ fn create_signature(args: &ConcretiseTypeArgs, old_signature: &Signature) -> Sig
     }
 }
 
-fn create_function_call(sig: &Signature) -> ExprCall {
+// The type check, argument bindings and function call for one combination of concrete
+// field types. The `cfg` attribute is empty unless an entry of the combination is conditional.
+struct Branch {
+    cfg: proc_macro2::TokenStream,
+    // The predicates that the `cfg` attribute requires.
+    cfg_predicates: Vec<proc_macro2::TokenStream>,
+    // The types of the objects that the wrappers must hold.
+    field_types: Vec<String>,
+    condition: proc_macro2::TokenStream,
+    bindings: proc_macro2::TokenStream,
+    call: proc_macro2::TokenStream,
+}
+
+// Creates documentation for the generated function that lists the types that the wrapper
+// arguments accept, the values that enum and const arguments accept and the behaviour if
+// an argument is not supported.
+fn create_documentation(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    new_signature: &Signature,
+    branches: &[Branch],
+) -> Vec<String> {
+    let mut doc = Vec::new();
+
+    if !args.field.is_empty() {
+        doc.push(" The wrapper arguments accept objects of the following types:".to_string());
+        for (index, field) in args.field.iter().enumerate() {
+            let arg = sig
+                .inputs
+                .get(field.arg)
+                .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+            let ident = get_function_arg_ident(arg);
+
+            let mut types = Vec::new();
+            for branch in branches.iter() {
+                let mut ty = format!("`{}`", branch.field_types[index]);
+                if !branch.cfg_predicates.is_empty() {
+                    let predicates = branch
+                        .cfg_predicates
+                        .iter()
+                        .map(|predicate| format!("`{}`", predicate))
+                        .join(" and ");
+                    ty = format!("{} (if {})", ty, predicates);
+                }
+                if !types.contains(&ty) {
+                    types.push(ty);
+                }
+            }
+
+            let wrapper = if get_field_kind(arg) == FieldKind::Slice {
+                format!(
+                    "array of `{}` with length `{}`",
+                    field.wrapper,
+                    get_slice_len_ident(ident)
+                )
+            } else {
+                format!("`{}`", field.wrapper)
+            };
+
+            doc.push(format!(
+                " - `{}` ({}): {}",
+                ident,
+                wrapper,
+                types.join(", ")
+            ));
+        }
+        if args.field.len() > 1 {
+            doc.push(String::new());
+            doc.push(format!(
+                " Only the combinations listed by `{}_supported_types` are supported.",
+                new_signature.ident
+            ));
+        }
+    }
+
+    let gen_values = args.gen_values();
+    if gen_values.iter().any(|x| x.arg.is_some()) {
+        if !doc.is_empty() {
+            doc.push(String::new());
+        }
+        doc.push(" The following arguments accept the given values:".to_string());
+        for gen_value in gen_values.iter() {
+            let Some(arg) = gen_value.arg else {
+                continue;
+            };
+            let arg = sig
+                .inputs
+                .get(arg)
+                .unwrap_or_else(|| panic!("Argument {} does not exist.", arg));
+            let values = gen_value
+                .entries
+                .iter()
+                .map(|entry| {
+                    // Enum arguments accept the variants, const arguments accept the values.
+                    let value = if gen_value.is_enum {
+                        format!("`{}`", entry.pattern.to_string().replace(" :: ", "::"))
+                    } else {
+                        format!("`{}`", entry.value)
+                    };
+                    match &entry.cfg {
+                        Some(predicate) => format!("{} (if `{}`)", value, predicate),
+                        None => value,
+                    }
+                })
+                .join(", ");
+            doc.push(format!(" - `{}`: {}", get_function_arg_ident(arg), values));
+        }
+    }
+
+    if !args.scalar.is_empty() {
+        if !doc.is_empty() {
+            doc.push(String::new());
+        }
+        let scalars = args
+            .scalar
+            .iter()
+            .map(|scalar| format!("`{}`", get_function_arg_ident(&sig.inputs[scalar.arg])))
+            .join(", ");
+        doc.push(format!(
+            " The scalar arguments {} must have the dtype that the implementation expects.",
+            scalars
+        ));
+    }
+
+    let error_value = match &new_signature.output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(_, ty) => Some(match ty.as_ref() {
+            Type::Ptr(_) => "a null pointer".to_string(),
+            ty => match quote! { #ty }.to_string().as_str() {
+                "f32" | "f64" => "NaN".to_string(),
+                "bool" => "false".to_string(),
+                "c_api_tools :: CScalar" => "a scalar with dtype `Undefined`".to_string(),
+                "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" => {
+                    "0".to_string()
+                }
+                _ => "the error value of the return type".to_string(),
+            },
+        }),
+    };
+
+    if !doc.is_empty() {
+        doc.push(String::new());
+    }
+    doc.push(
+        " If an argument is not supported, an error is set that can be retrieved with".to_string(),
+    );
+    match error_value {
+        Some(error_value) => doc.push(format!(
+            " `c_api_tools_last_error` and {} is returned.",
+            error_value
+        )),
+        None => doc.push(" `c_api_tools_last_error`.".to_string()),
+    }
+
+    doc
+}
+
+// Creates the function `<fn>_batch`, which applies `<fn>` to every wrapper in an array.
+// The result of each call is written to `results` (if `<fn>` returns a value) and a status
+// code (0 for success, 1 for an error) is written to `status`. With `batched = "parallel"`
+// the calls are distributed over threads if the `parallel` feature is enabled.
+fn create_batch_function(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    block: &syn::Block,
+    vis: &syn::Visibility,
+    new_signature: &Signature,
+    branches: &[Branch],
+) -> proc_macro2::TokenStream {
+    let version_attributes = create_version_attributes(&args.deprecated, &args.since);
+    if args.field.len() != 1 {
+        panic!("Batched functions must have exactly one field.");
+    }
+    let field = &args.field[0];
+
+    let arg = sig
+        .inputs
+        .get(field.arg)
+        .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+
+    if !matches!(get_field_kind(arg), FieldKind::Ref | FieldKind::RefMut) {
+        panic!("The field of a batched function must be a reference.");
+    }
+
+    let parallel = match &args.batched {
+        Some(Override::Explicit(mode)) if mode == "parallel" => true,
+        Some(Override::Explicit(mode)) => panic!(
+            "`batched` must be set without a value or to \"parallel\", not \"{}\".",
+            mode
+        ),
+        _ => false,
+    };
+
+    let ident = get_function_arg_ident(arg);
+    let len_ident = get_slice_len_ident(ident);
+    let wrapper = Ident::new(&field.wrapper, Span::call_site());
+
+    let batch_ident = format_ident!("{}_batch", new_signature.ident);
+    let mut inputs = Punctuated::<FnArg, Token![,]>::new();
+    for arg in new_signature.inputs.iter() {
+        if get_function_arg_ident(arg) == ident && field.handle {
+            inputs.push(parse_quote! { #ident: *const u64 });
+            inputs.push(parse_quote! { #len_ident: usize });
+        } else if get_function_arg_ident(arg) == ident {
+            inputs.push(parse_quote! { #ident: *const *mut #wrapper });
+            inputs.push(parse_quote! { #len_ident: usize });
+        } else {
+            inputs.push(arg.clone());
+        }
+    }
+
+    let (output_type, result_pat, write_result) = match &new_signature.output {
+        syn::ReturnType::Default => (quote! { () }, quote! { _ }, quote! {}),
+        syn::ReturnType::Type(_, ty) => {
+            inputs.push(parse_quote! { results: *mut #ty });
+            (
+                quote! { #ty },
+                quote! { result },
+                quote! {
+                    if !results.is_null() {
+                        *results.add(index) = result;
+                    }
+                },
+            )
+        }
+    };
+    inputs.push(parse_quote! { status: *mut i32 });
+
+    let mut dispatch = quote! {};
+
+    for Branch {
+        cfg,
+        condition,
+        bindings,
+        call,
+        ..
+    } in branches.iter()
+    {
+        dispatch = quote! {
+            #dispatch
+            #cfg
+            if #condition {
+                #bindings
+                return Ok(#call);
+            }
+        };
+    }
+
+    let handle_checks = create_handle_checks(args, sig);
+    let borrow_checks = create_borrow_checks(args, sig);
+    let value_checks = create_value_checks(args, sig);
+    let mismatch_errors = create_mismatch_errors(args, sig, branches, quote! { Err(()) });
+    // The handles are pinned until all calls have returned.
+    let elements = if field.handle {
+        quote! {
+            let pins = (0..#len_ident)
+                .map(|index| #wrapper::pin_handle(*#ident.add(index)))
+                .collect::<Vec<_>>();
+            let elements = pins
+                .iter()
+                .map(|pin| pin.as_ref().map_or(std::ptr::null_mut(), |pin| pin.get()))
+                .collect::<Vec<*mut #wrapper>>();
+        }
+    } else {
+        quote! {
+            let elements = (0..#len_ident)
+                .map(|index| *#ident.add(index))
+                .collect::<Vec<*mut #wrapper>>();
+        }
+    };
+    // A wrapper that appears twice in the array would be borrowed mutably by two calls.
+    let duplicate_check = if get_field_kind(arg) == FieldKind::RefMut {
+        let message = format!(
+            "Elements of `{}` point to the same wrapper, which is borrowed mutably.",
+            ident
+        );
+        quote! {
+            let mut distinct = std::collections::HashSet::new();
+            if elements
+                .iter()
+                .any(|element| !element.is_null() && !distinct.insert(*element))
+            {
+                for index in 0..#len_ident {
+                    write_status(index, 1);
+                }
+                c_api_tools::set_last_error(#message);
+                return;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let mut doc = vec![
+        format!(
+            " Apply `{}` to every wrapper in the array `{}` of length `{}`.",
+            new_signature.ident, ident, len_ident
+        ),
+        String::new(),
+        " The remaining arguments are passed unchanged to every call. If `status` is not null,"
+            .to_string(),
+        " the status code 0 is written to it for every element whose call succeeded and 1 for"
+            .to_string(),
+        " every element whose call failed.".to_string(),
+    ];
+    if matches!(new_signature.output, syn::ReturnType::Type(..)) {
+        doc.push(" If `results` is not null, the value of each call is written to it.".to_string());
+    }
+    doc.push(" If a call fails, the error of the first element that failed is set.".to_string());
+    doc.push(format!(
+        " If `{}` is null and `{}` is not 0, an error is set and no call is made.",
+        ident, len_ident
+    ));
+    if get_field_kind(arg) == FieldKind::RefMut {
+        doc.push(
+            " The wrappers in the array must be distinct, otherwise an error is set and no call"
+                .to_string(),
+        );
+        doc.push(" is made.".to_string());
+    }
+    if parallel {
+        doc.push(
+            " If the `parallel` feature of `c-api-tools` is enabled, the calls run in parallel."
+                .to_string(),
+        );
+    }
+
+    // Parallel calls are only made for wrappers that can be shared between threads.
+    let parallel_assertion = if parallel {
+        let message = format!(
+            "`batched = \"parallel\"` requires a wrapper with `sync` or `borrow_check`, but `{}` has neither.",
+            field.wrapper
+        );
+        quote! {
+            const _: () = assert!(#wrapper::thread_safe(), #message);
+        }
+    } else {
+        quote! {}
+    };
+    let null_message = format!("Argument `{}` is a null pointer.", ident);
+
+    quote! {
+        #(#[doc = #doc])*
+        #version_attributes
+        #[no_mangle]
+        #vis unsafe extern "C" fn #batch_ident(#inputs) {
+            #vis #sig
+            #block
+
+            #parallel_assertion
+
+            let write_status = |index: usize, code: i32| {
+                if !status.is_null() {
+                    *status.add(index) = code;
+                }
+            };
+            if #len_ident > 0 && #ident.is_null() {
+                for index in 0..#len_ident {
+                    write_status(index, 1);
+                }
+                c_api_tools::set_last_error(#null_message);
+                return;
+            }
+
+            let call = |#ident: *mut #wrapper| -> Result<#output_type, ()> {
+                #handle_checks
+                #borrow_checks
+                #value_checks
+                #dispatch
+                #mismatch_errors
+            };
+
+            #elements
+            #duplicate_check
+
+            // Errors are set on the thread that made the call, so they are collected and the
+            // error of the first element that failed is set on the calling thread.
+            let errors = std::sync::Mutex::new(Vec::new());
+            c_api_tools::__private::for_each_index(#len_ident, #parallel, |index| {
+                match call(elements[index]) {
+                    Ok(#result_pat) => {
+                        #write_result
+                        write_status(index, 0);
+                    }
+                    Err(()) => {
+                        write_status(index, 1);
+                        let message = c_api_tools::take_last_error().unwrap_or_default();
+                        errors.lock().unwrap().push((index, message));
+                    }
+                }
+            });
+            if let Some((index, message)) = errors
+                .into_inner()
+                .unwrap()
+                .into_iter()
+                .min_by_key(|(index, _)| *index)
+            {
+                c_api_tools::set_last_error(&format!("Element {}: {}", index, message));
+            }
+        }
+    }
+}
+
+// Returns the arguments of the new signature that select the branch: the fields and the
+// arguments of `gen_enum` and `gen_const`.
+fn get_dispatch_inputs(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    new_signature: &Signature,
+) -> Vec<FnArg> {
+    let mut field_idents = Vec::new();
+    for field in args.field.iter() {
+        let arg = sig
+            .inputs
+            .get(field.arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", field.arg));
+        let ident = get_function_arg_ident(arg);
+        if get_field_kind(arg) == FieldKind::Slice {
+            field_idents.push(get_slice_len_ident(ident));
+        }
+        field_idents.push(ident.clone());
+    }
+    for arg in args.gen_values().iter().filter_map(|x| x.arg) {
+        let arg = sig
+            .inputs
+            .get(arg)
+            .unwrap_or_else(|| panic!("Argument {} does not exist.", arg));
+        field_idents.push(get_function_arg_ident(arg).clone());
+    }
+
+    new_signature
+        .inputs
+        .iter()
+        .filter(|arg| field_idents.contains(get_function_arg_ident(arg)))
+        .cloned()
+        .collect()
+}
+
+// Creates the functions `<fn>_supported_types`, which returns the supported combinations of
+// field types one at a time, and `<fn>_supports`, which checks if `<fn>` supports the given
+// arguments without calling it.
+fn create_query_functions(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    vis: &syn::Visibility,
+    new_signature: &Signature,
+    branches: &[Branch],
+) -> proc_macro2::TokenStream {
+    let version_attributes = create_version_attributes(&args.deprecated, &args.since);
+    let field_inputs = get_dispatch_inputs(args, sig, new_signature);
+
+    let ident = &new_signature.ident;
+    let supported_types_ident = format_ident!("{}_supported_types", ident);
+    let supports_ident = format_ident!("{}_supports", ident);
+    let invalid_handles = create_invalid_handle_conditions(args, sig)
+        .into_iter()
+        .map(|(_, condition)| condition)
+        .collect_vec();
+    let handle_resolution = create_handle_resolution(args, sig);
+    let enum_conversions = create_enum_conversions(args, sig, |_| quote! { return false });
+    let handle_checks = if invalid_handles.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            if #(#invalid_handles)||* {
+                return false;
+            }
+        }
+    };
+
+    let mut entries = quote! {};
+    let mut conditions = quote! {};
+
+    for Branch {
+        cfg,
+        field_types,
+        condition,
+        ..
+    } in branches.iter()
+    {
+        let entry = field_types.join(", ") + "\0";
+        entries = quote! {
+            #entries
+            #cfg
+            if !supported.contains(&#entry) {
+                supported.push(#entry);
+            }
+        };
+        conditions = quote! {
+            #conditions
+            #cfg
+            if #condition {
+                return true;
+            }
+        };
+    }
+
+    let supported_types_doc = [
+        format!(
+            " Return the combination of types with the given index that `{}` supports.",
+            ident
+        ),
+        String::new(),
+        " A combination lists the types of the wrapped objects of all wrapper arguments,"
+            .to_string(),
+        " separated by commas. Returns a null pointer if the index is out of range.".to_string(),
+    ];
+
+    let supports_doc = [
+        format!(
+            " Return true if `{}` supports the types of the given arguments.",
+            ident
+        ),
+        String::new(),
+        format!(
+            " This does not call `{}` and does not set an error. Returns false if",
+            ident
+        ),
+        " a wrapper argument is an invalid handle.".to_string(),
+    ];
+
+    quote! {
+        #(#[doc = #supported_types_doc])*
+        #version_attributes
+        #[no_mangle]
+        #vis extern "C" fn #supported_types_ident(index: usize) -> *const std::ffi::c_char {
+            #[allow(unused_mut)]
+            let mut supported: Vec<&'static str> = Vec::new();
+            #entries
+            supported
+                .get(index)
+                .map_or(std::ptr::null(), |entry| entry.as_ptr() as *const std::ffi::c_char)
+        }
+
+        #(#[doc = #supports_doc])*
+        #version_attributes
+        #[no_mangle]
+        #vis unsafe extern "C" fn #supports_ident(#(#field_inputs),*) -> bool {
+            #handle_resolution
+            #handle_checks
+            #enum_conversions
+            #conditions
+            false
+        }
+    }
+}
+
+// Creates the function `<fn>_resolve`, which takes only the wrapper arguments and returns
+// a function pointer to a version of `<fn>` specialised for the concrete types of the
+// wrapped objects.
+fn create_resolve_function(
+    args: &ConcretiseTypeArgs,
+    sig: &Signature,
+    block: &syn::Block,
+    vis: &syn::Visibility,
+    new_signature: &Signature,
+    branches: &[Branch],
+) -> proc_macro2::TokenStream {
+    let version_attributes = create_version_attributes(&args.deprecated, &args.since);
+    let field_inputs = get_dispatch_inputs(args, sig, new_signature);
+
+    let input_types = new_signature.inputs.iter().map(|arg| {
+        if let FnArg::Typed(arg) = arg {
+            &arg.ty
+        } else {
+            panic!("Argument must be typed.");
+        }
+    });
+
+    let ident = &new_signature.ident;
+    let resolve_ident = format_ident!("{}_resolve", ident);
+    let inputs = &new_signature.inputs;
+    let output = &new_signature.output;
+
+    let handle_resolution = create_handle_resolution(args, sig);
+    let handle_checks = create_handle_checks(args, sig);
+    let alias_checks = create_alias_checks(args, sig);
+    let borrow_checks = create_borrow_checks(args, sig);
+    let slice_checks = create_slice_checks(args, sig);
+    let value_checks = create_value_checks(args, sig);
+    let mut resolve_stream = quote! {};
+
+    for Branch {
+        cfg,
+        condition,
+        bindings,
+        call,
+        ..
+    } in branches.iter()
+    {
+        resolve_stream = quote! {
+            #resolve_stream
+            #cfg
+            if #condition {
+                unsafe extern "C" fn resolved(#inputs) #output {
+                    // The resolved function can be called with other wrappers than
+                    // `<fn>_resolve`, so the checks that do not depend on the types are
+                    // repeated.
+                    #handle_resolution
+                    #handle_checks
+                    #alias_checks
+                    #borrow_checks
+                    #slice_checks
+                    #value_checks
+                    #bindings
+                    #call
+                }
+                return Some(resolved);
+            }
+        };
+    }
+
+    let mismatch_errors = create_mismatch_errors(args, sig, branches, quote! { None });
+
+    let doc = [
+        format!(
+            " Resolve the concrete types of the wrapped objects passed to `{}`.",
+            ident
+        ),
+        String::new(),
+        format!(
+            " Returns a function with the same arguments as `{}` that calls the implementation",
+            ident
+        ),
+        " for these types without dispatching on them. The returned function must only be"
+            .to_string(),
+        " called with wrappers that hold objects of the same types. If the types are not"
+            .to_string(),
+        " supported, an error is set and a null pointer is returned.".to_string(),
+    ];
+
+    quote! {
+        #(#[doc = #doc])*
+        #version_attributes
+        #[no_mangle]
+        #vis unsafe extern "C" fn #resolve_ident(#(#field_inputs),*) -> Option<unsafe extern "C" fn(#(#input_types),*) #output> {
+            #vis #sig
+            #block
+
+            #handle_resolution
+            #handle_checks
+            #alias_checks
+            #borrow_checks
+            #slice_checks
+            #value_checks
+            #resolve_stream
+            #mismatch_errors
+        }
+    }
+}
+
+// Creates the generic arguments for a call of the function with signature `sig`. Generic
+// parameters whose name is in `keys` are replaced with the corresponding entry of `values`,
+// all others are inferred. If no generic parameter is in `keys` no arguments are created.
+pub(crate) fn create_generic_arguments(
+    sig: &Signature,
+    keys: &[String],
+    values: &[String],
+) -> syn::PathArguments {
+    let mut generic_args = Punctuated::<syn::GenericArgument, Token![,]>::new();
+    let mut is_bound = false;
+
+    for param in sig.generics.params.iter() {
+        let (param_ident, is_const) = match param {
+            syn::GenericParam::Type(param) => (&param.ident, false),
+            syn::GenericParam::Const(param) => (&param.ident, true),
+            syn::GenericParam::Lifetime(_) => continue,
+        };
+
+        if let Some((_, value)) =
+            izip!(keys.iter(), values.iter()).find(|(key, _)| param_ident == key.as_str())
+        {
+            let value = value.parse::<proc_macro2::TokenStream>().unwrap();
+            if is_const {
+                // Const values are wrapped in braces so that any expression is accepted.
+                generic_args.push(parse_quote! { { #value } });
+            } else {
+                generic_args.push(parse_quote! { #value });
+            }
+            is_bound = true;
+        } else {
+            generic_args.push(parse_quote! { _ });
+        }
+    }
+
+    if is_bound {
+        syn::PathArguments::AngleBracketed(parse_quote! { ::<#generic_args> })
+    } else {
+        syn::PathArguments::None
+    }
+}
+
+fn create_function_call(sig: &Signature, generic_args: syn::PathArguments) -> ExprCall {
     // We go through the signature and build from it a function call sequence.
 
     let mut punctuated = Punctuated::<Expr, Token![,]>::new();
@@ -202,7 +1755,7 @@ fn create_function_call(sig: &Signature) -> ExprCall {
                 let mut punctuated = Punctuated::new();
                 punctuated.push(syn::PathSegment {
                     ident: sig.ident.clone(),
-                    arguments: syn::PathArguments::None,
+                    arguments: generic_args,
                 });
                 punctuated
             },
@@ -238,11 +1791,176 @@ fn create_function_call(sig: &Signature) -> ExprCall {
     }
 }
 
+// An entry of a `replace_with` list. An entry is either a string or has the form
+// `cfg(<predicate>, "...")`, in which case the code generated for the entry is only
+// compiled if the predicate holds.
+pub(crate) struct ReplaceWith {
+    pub(crate) value: String,
+    pub(crate) cfg: Option<proc_macro2::TokenStream>,
+}
+
+impl FromMeta for ReplaceWith {
+    fn from_expr(expr: &Expr) -> darling::Result<Self> {
+        match expr {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(value),
+                ..
+            }) => Ok(ReplaceWith {
+                value: value.value(),
+                cfg: None,
+            }),
+            Expr::Call(call) if matches!(call.func.as_ref(), Expr::Path(path) if path.path.is_ident("cfg")) =>
+            {
+                let (
+                    Some(predicate),
+                    Some(Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(value),
+                        ..
+                    })),
+                    None,
+                ) = (call.args.get(0), call.args.get(1), call.args.get(2))
+                else {
+                    return Err(Error::custom(
+                        "Conditional entries must have the form `cfg(<predicate>, \"...\")`.",
+                    )
+                    .with_span(expr));
+                };
+                Ok(ReplaceWith {
+                    value: value.value(),
+                    cfg: Some(quote! { #predicate }),
+                })
+            }
+            _ => Err(Error::unexpected_expr_type(expr)),
+        }
+    }
+}
+
+// A `replace_with` list whose entries can be conditional.
+#[derive(Default)]
+pub(crate) struct ReplaceWithList(Vec<ReplaceWith>);
+
+impl FromMeta for ReplaceWithList {
+    fn from_expr(expr: &Expr) -> darling::Result<Self> {
+        match expr {
+            Expr::Array(array) => array
+                .elems
+                .iter()
+                .map(ReplaceWith::from_expr)
+                .collect::<darling::Result<_>>()
+                .map(ReplaceWithList),
+            _ => Err(Error::unexpected_expr_type(expr)),
+        }
+    }
+}
+
+impl std::ops::Deref for ReplaceWithList {
+    type Target = [ReplaceWith];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// Creates a `#[cfg]` attribute that requires all given predicates. If there are no predicates
+// no attribute is created.
+pub(crate) fn create_cfg_attribute<'a>(
+    predicates: impl IntoIterator<Item = &'a proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let predicates = predicates.into_iter().collect_vec();
+    if predicates.is_empty() {
+        quote! {}
+    } else {
+        quote! { #[cfg(all(#(#predicates),*))] }
+    }
+}
+
 #[derive(Default, FromMeta)]
 #[darling(default)]
 struct GenType {
     name: String,
-    replace_with: Vec<syn::LitStr>,
+    replace_with: ReplaceWithList,
+    coerce: ReplaceWithList,
+}
+
+// A choice for a generic type. Wrapped objects are stored with the type `stored` and the
+// function is called with the type `target`. The types only differ for `coerce` entries.
+struct GenTypeEntry<'a> {
+    stored: String,
+    target: String,
+    cfg: Option<&'a proc_macro2::TokenStream>,
+}
+
+impl GenType {
+    // Return the types in `replace_with` followed by the coercions in `coerce`.
+    fn entries(&self) -> Vec<GenTypeEntry<'_>> {
+        let exact = self.replace_with.iter().map(|x| GenTypeEntry {
+            stored: x.value.clone(),
+            target: x.value.clone(),
+            cfg: x.cfg.as_ref(),
+        });
+
+        let coerced = self.coerce.iter().map(|x| {
+            let (stored, target) = x.value.split_once("->").unwrap_or_else(|| {
+                panic!(
+                    "Coercion `{}` must have the form `stored -> target`.",
+                    x.value
+                )
+            });
+            GenTypeEntry {
+                stored: stored.trim().to_string(),
+                target: target.trim().to_string(),
+                cfg: x.cfg.as_ref(),
+            }
+        });
+
+        exact.chain(coerced).collect()
+    }
+}
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+struct GenEnum {
+    name: String,
+    arg: usize,
+    replace_with: ReplaceWithList,
+    // The integer type that C passes for the enum. Defaults to `std::ffi::c_int`, which
+    // is the representation of `#[repr(C)]` enums.
+    repr: Option<String>,
+}
+
+impl GenEnum {
+    fn repr(&self) -> proc_macro2::TokenStream {
+        self.repr
+            .as_deref()
+            .unwrap_or("std::ffi::c_int")
+            .parse()
+            .unwrap()
+    }
+}
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+struct GenConst {
+    name: String,
+    arg: Option<usize>,
+    replace_with: Vec<usize>,
+}
+
+// A generic parameter that is bound to a value selected by an enum argument (`gen_enum`)
+// or to a constant that can optionally be selected by an argument (`gen_const`).
+struct GenValue {
+    name: String,
+    arg: Option<usize>,
+    entries: Vec<GenValueEntry>,
+    // Enum arguments are passed from C as integers.
+    is_enum: bool,
+}
+
+// The pattern that the argument of a `GenValue` is matched against and the corresponding value.
+struct GenValueEntry {
+    pattern: proc_macro2::TokenStream,
+    value: String,
+    cfg: Option<proc_macro2::TokenStream>,
 }
 
 #[derive(Default, FromMeta)]
@@ -251,7 +1969,18 @@ struct Field {
     arg: usize,
     name: String,
     wrapper: String,
-    replace_with: Vec<syn::LitStr>,
+    replace_with: ReplaceWithList,
+    free: bool,
+    // The wrapper is passed from C as an integer handle. The wrapper must use
+    // `handles = "table"`.
+    handle: bool,
+}
+
+// An argument of a generic scalar type that is passed from C as a `CScalar`.
+#[derive(Default, FromMeta)]
+#[darling(default)]
+struct Scalar {
+    arg: usize,
 }
 
 #[derive(Default, FromMeta)]
@@ -260,7 +1989,60 @@ struct ConcretiseTypeArgs {
     #[darling(multiple)]
     gen_type: Vec<GenType>,
     #[darling(multiple)]
+    gen_enum: Vec<GenEnum>,
+    #[darling(multiple)]
+    gen_const: Vec<GenConst>,
+    #[darling(multiple)]
     field: Vec<Field>,
+    #[darling(multiple)]
+    scalar: Vec<Scalar>,
+    return_scalar: bool,
+    resolve: bool,
+    // `batched` makes the calls on the calling thread, `batched = "parallel"` distributes
+    // them over threads.
+    batched: Option<Override<String>>,
+    deprecated: Option<String>,
+    since: Option<String>,
+}
+
+impl ConcretiseTypeArgs {
+    // Return the generic parameters bound by `gen_enum` and `gen_const`.
+    fn gen_values(&self) -> Vec<GenValue> {
+        let enums = self.gen_enum.iter().map(|x| GenValue {
+            name: x.name.clone(),
+            arg: Some(x.arg),
+            is_enum: true,
+            entries: x
+                .replace_with
+                .iter()
+                .map(|x| {
+                    let (pattern, value) = parse_enum_entry(&x.value);
+                    GenValueEntry {
+                        pattern,
+                        value,
+                        cfg: x.cfg.clone(),
+                    }
+                })
+                .collect(),
+        });
+
+        let consts = self.gen_const.iter().map(|x| GenValue {
+            name: x.name.clone(),
+            arg: x.arg,
+            is_enum: false,
+            entries: x
+                .replace_with
+                .iter()
+                .map(|x| GenValueEntry {
+                    pattern: quote! { #x },
+                    value: x.to_string(),
+                    cfg: None,
+                })
+                .collect(),
+        });
+
+        enums.chain(consts).collect()
+    }
 }
 
 pub(crate) fn concretise_type_impl(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -286,89 +2068,264 @@ pub(crate) fn concretise_type_impl(args: TokenStream, item: TokenStream) -> Toke
         }
     };
 
-    let ConcretiseTypeArgs { gen_type, field } = &args;
+    let ConcretiseTypeArgs {
+        gen_type, field, ..
+    } = &args;
+
+    let gen_values = args.gen_values();
 
     let gen_keys = gen_type.iter().map(|x| x.name.clone()).collect_vec();
+    let value_keys = gen_values.iter().map(|x| x.name.clone()).collect_vec();
     let field_keys = field.iter().map(|x| x.name.clone()).collect_vec();
 
     // We are first preparing the new signature.
     // The new signature replaces template types with the wrapper pointer types.
     let new_signature = create_signature(&args, &sig);
 
-    let call_expr = create_function_call(&new_signature);
+    let handle_assertions = create_handle_assertions(&args);
+    let handle_resolution = create_handle_resolution(&args, &sig);
+    let handle_checks = create_handle_checks(&args, &sig);
+    let alias_checks = create_alias_checks(&args, &sig);
+    let borrow_checks = create_borrow_checks(&args, &sig);
+    let slice_checks = create_slice_checks(&args, &sig);
+    let value_checks = create_value_checks(&args, &sig);
 
     // We start preparing the output quote. This will contain the new signature
 
     // We are now doing a cartesian iterator over the gen types and within this a cartesion
     // iterator over the field types.
 
-    let mut if_let_stream = quote! {};
+    let mut branches = Vec::new();
+    // Branches that convert objects are tried after all branches for exact types.
+    let mut coerced_branches = Vec::new();
 
-    for gen_it in gen_type
+    let gen_entries = gen_type.iter().map(|x| x.entries()).collect_vec();
+
+    for gen_it in gen_entries
         .iter()
-        .map(|x| x.replace_with.iter().map(|x| x.value().clone()))
+        .map(|x| x.iter())
         .multi_cartesian_product()
         .peekable()
     {
         // Replace the generic types in order. A later type can only depend on earlier types.
 
-        let complete_gen_types = replace_templates_with_types(&gen_keys, &gen_it);
+        let gen_types = gen_it.iter().map(|x| x.target.clone()).collect_vec();
+        let complete_gen_types = replace_templates_with_types(&gen_keys, &gen_types);
+        let stored_gen_types = gen_it.iter().map(|x| x.stored.clone()).collect_vec();
+        let stored_gen_types = replace_templates_with_types(&gen_keys, &stored_gen_types);
 
-        // Now we iterate over the field types. The field types are the types that get replaced
-        // in the index list with the corresponding Wrapper types.
+        // Each combination of enum variants and constants binds the value keys.
 
-        for field_type_it in field
+        for value_it in gen_values
             .iter()
-            .map(|x| x.replace_with.iter().map(|x| x.value().clone()))
+            .map(|x| x.entries.iter())
             .multi_cartesian_product()
-            .peekable()
         {
-            // First we replace the generic keys in the field types.
+            let keys = gen_keys
+                .iter()
+                .chain(value_keys.iter())
+                .cloned()
+                .collect_vec();
+            let values = complete_gen_types
+                .iter()
+                .cloned()
+                .chain(value_it.iter().map(|entry| entry.value.clone()))
+                .collect_vec();
+            let stored_values = stored_gen_types
+                .iter()
+                .cloned()
+                .chain(value_it.iter().map(|entry| entry.value.clone()))
+                .collect_vec();
+
+            let value_conditions = izip!(gen_values.iter(), value_it.iter())
+                .filter_map(|(gen_value, entry)| {
+                    let arg = sig
+                        .inputs
+                        .get(gen_value.arg?)
+                        .unwrap_or_else(|| panic!("Argument {:?} does not exist.", gen_value.arg));
+                    let ident = get_function_arg_ident(arg);
+                    let pattern = &entry.pattern;
+                    Some(quote! { matches!(#ident, #pattern) })
+                })
+                .collect_vec();
+
+            // The call uses the original signature as slice fields add length arguments
+            // to the new signature.
+            let call = create_function_call(&sig, create_generic_arguments(&sig, &keys, &values));
+            let call = if args.return_scalar {
+                quote! { c_api_tools::CScalar::from(#call) }
+            } else {
+                quote! { #call }
+            };
+
+            // Now we iterate over the field types. The field types are the types that get replaced
+            // in the index list with the corresponding Wrapper types.
 
-            let mut complete_field_types = field_type_it.clone();
+            for field_type_it in field
+                .iter()
+                .map(|x| x.replace_with.iter())
+                .multi_cartesian_product()
+                .peekable()
+            {
+                // First we replace the generic keys in the field types. This is done
+                // for the stored types and for the types that the function is called with.
 
-            for (key, complete_gen_type) in izip!(gen_keys.iter(), complete_gen_types.iter()) {
-                for field in complete_field_types.iter_mut() {
-                    *field = field.replace(&("{{".to_owned() + key + "}}"), complete_gen_type);
+                let mut complete_field_types =
+                    field_type_it.iter().map(|x| x.value.clone()).collect_vec();
+                let mut target_field_types = complete_field_types.clone();
+
+                for (key, value, stored_value) in
+                    izip!(keys.iter(), values.iter(), stored_values.iter())
+                {
+                    let key = "{{".to_owned() + key + "}}";
+                    for field in complete_field_types.iter_mut() {
+                        *field = field.replace(&key, stored_value);
+                    }
+                    for field in target_field_types.iter_mut() {
+                        *field = field.replace(&key, value);
+                    }
                 }
-            }
 
-            // We now substitute within the field types.
+                // We now substitute within the field types.
 
-            let complete_field_types =
-                replace_templates_with_types(&field_keys, &complete_field_types);
+                let complete_field_types =
+                    replace_templates_with_types(&field_keys, &complete_field_types);
+                let target_field_types =
+                    replace_templates_with_types(&field_keys, &target_field_types);
 
-            // We now have the complete field types. Let us build the corresponding if let statement.
-            //
+                // Objects behind mutable references cannot be converted.
+                let coerced = complete_field_types != target_field_types;
+                if izip!(
+                    field.iter(),
+                    complete_field_types.iter(),
+                    target_field_types.iter()
+                )
+                .any(|(field, stored, target)| {
+                    stored != target && get_field_kind(&sig.inputs[field.arg]) == FieldKind::RefMut
+                }) {
+                    continue;
+                }
 
-            let if_let = create_if_let_condition(&args, &complete_field_types, &sig, &call_expr);
+                // We now have the complete field types. Let us build the corresponding if let statement.
+                //
 
-            if_let_stream = quote! {
-                #if_let_stream
-                #if_let
+                let type_condition = create_type_condition(&args, &complete_field_types, &sig);
 
-            }
+                // The branch is only compiled if all conditional entries are enabled.
+                let cfg_predicates = gen_it
+                    .iter()
+                    .filter_map(|x| x.cfg)
+                    .chain(field_type_it.iter().filter_map(|x| x.cfg.as_ref()))
+                    .chain(value_it.iter().filter_map(|entry| entry.cfg.as_ref()))
+                    .cloned()
+                    .collect_vec();
 
-            //
+                let branch = Branch {
+                    cfg: create_cfg_attribute(&cfg_predicates),
+                    cfg_predicates,
+                    field_types: complete_field_types.clone(),
+                    condition: quote! { #(#value_conditions &&)* #type_condition },
+                    bindings: create_bindings(
+                        &args,
+                        &complete_field_types,
+                        &target_field_types,
+                        &sig,
+                    ),
+                    call: call.clone(),
+                };
+
+                if coerced {
+                    coerced_branches.push(branch);
+                } else {
+                    branches.push(branch);
+                }
+            }
         }
     }
 
+    branches.extend(coerced_branches);
+
+    let mut if_let_stream = quote! {};
+
+    for Branch {
+        cfg,
+        condition,
+        bindings,
+        call,
+        ..
+    } in branches.iter()
+    {
+        if_let_stream = quote! {
+            #if_let_stream
+            #cfg
+            if #condition {
+                #bindings
+                return #call;
+            }
+        };
+    }
+
+    let mismatch_errors = create_mismatch_errors(
+        &args,
+        &sig,
+        &branches,
+        quote! { c_api_tools::ErrorValue::error_value() },
+    );
+
     // We now put everything together.
 
+    let resolve = if args.resolve {
+        create_resolve_function(&args, &sig, &block, &vis, &new_signature, &branches)
+    } else {
+        quote! {}
+    };
+
+    let queries = create_query_functions(&args, &sig, &vis, &new_signature, &branches);
+
+    let batch = if args.batched.is_some() {
+        create_batch_function(&args, &sig, &block, &vis, &new_signature, &branches)
+    } else {
+        quote! {}
+    };
+
+    let doc = create_documentation(&args, &sig, &new_signature, &branches);
+    let version_attributes = create_version_attributes(&args.deprecated, &args.since);
+    // The generated documentation is separated from the documentation of the function.
+    let separator = if attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+        quote! { #[doc = ""] }
+    } else {
+        quote! {}
+    };
+
     let output = quote! {
         #( #attrs)*
+        #separator
+        #(#[doc = #doc])*
+        #version_attributes
         #[no_mangle]
         #vis #new_signature {
            #vis #sig
            #block
 
+           #handle_assertions
+           #handle_resolution
+           #handle_checks
+           #alias_checks
+           #borrow_checks
+           #slice_checks
+           #value_checks
+
            #if_let_stream
-           {
-           panic!("Unknown type.");
-            }
+           #mismatch_errors
 
        }
 
+       #resolve
+
+       #batch
+
+       #queries
+
     };
 
     output.into()
@@ -3,9 +3,11 @@
 #![warn(missing_docs)]
 
 mod attribute_c_wrappers;
+mod attribute_concretise_constructor;
 mod attribute_concretise_types;
 
 use attribute_c_wrappers::c_wrapper_impl;
+use attribute_concretise_constructor::concretise_constructor_impl;
 use attribute_concretise_types::concretise_type_impl;
 use proc_macro::TokenStream;
 
@@ -20,3 +22,9 @@ pub fn cfuncs(args: TokenStream, item: TokenStream) -> TokenStream {
 pub fn concretise_types(args: TokenStream, item: TokenStream) -> TokenStream {
     concretise_type_impl(args, item)
 }
+
+/// Concretise constructor
+#[proc_macro_attribute]
+pub fn concretise_constructor(args: TokenStream, item: TokenStream) -> TokenStream {
+    concretise_constructor_impl(args, item)
+}
@@ -3,19 +3,1208 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/* Marks functions with the `deprecated` option as deprecated. */
+#if defined(__GNUC__) || defined(__clang__)
+#define DEPRECATED(note) __attribute__((deprecated(note)))
+#elif defined(_MSC_VER)
+#define DEPRECATED(note) __declspec(deprecated(note))
+#else
+#define DEPRECATED(note)
+#endif
+
+/* Error reporting functions exported by `c-api-tools`. */
+
+/**
+ * Return the last error message of the current thread, or NULL if there is none.
+ */
+const char *c_api_tools_last_error(void);
+
+/**
+ * Clear the last error message of the current thread.
+ */
+void c_api_tools_clear_last_error(void);
+
+/**
+ * Representation of scalar numeric type information
+ * as runtime parameter.
+ */
+enum DType {
+  /**
+   * Float 32
+   */
+  DType_F32,
+  /**
+   * Float 64
+   */
+  DType_F64,
+  /**
+   * Complex 32
+   */
+  DType_C32,
+  /**
+   * Complex 64
+   */
+  DType_C64,
+  /**
+   * Unsigned int 8
+   */
+  DType_U8,
+  /**
+   * Unsigned int 32
+   */
+  DType_U32,
+  /**
+   * Unsigned int 64
+   */
+  DType_U64,
+  /**
+   * Int 8
+   */
+  DType_I8,
+  /**
+   * Int 32
+   */
+  DType_I32,
+  /**
+   * Int 64
+   */
+  DType_I64,
+  /**
+   * Undefined type
+   */
+  DType_Undefined,
+};
+typedef uint8_t DType;
+
+/**
+ * Reduction of the fields of a struct.
+ */
+typedef enum Reduction {
+  /**
+   * Sum of the fields.
+   */
+  Reduction_Sum,
+  /**
+   * Product of the fields.
+   */
+  Reduction_Product,
+  /**
+   * Maximum of the fields.
+   */
+  Reduction_Maximum,
+} Reduction;
+
+/**
+ * Wrapper for `MyStruct` that rejects conflicting borrows at runtime.
+ */
+typedef struct CheckedWrapper CheckedWrapper;
+
+/**
+ * Thread-safe wrapper for `MyStruct` that is locked by a mutex.
+ */
+typedef struct MutexWrapper MutexWrapper;
+
 /**
  * Wrapper for `MyStruct`.
+ *
+ * Available since version 0.1.0.
  */
 typedef struct MyWrapper MyWrapper;
 
+/**
+ * Reference counted wrapper for `MyStruct`.
+ */
+typedef struct SharedWrapper SharedWrapper;
+
+/**
+ * Thread-safe wrapper for `MyStruct` that is locked by a read-write lock.
+ */
+typedef struct SyncWrapper SyncWrapper;
+
+/**
+ * Complex number with the layout of `float _Complex`.
+ */
+typedef struct CComplex32 {
+  /**
+   * Real part
+   */
+  float re;
+  /**
+   * Imaginary part
+   */
+  float im;
+} CComplex32;
+
+/**
+ * Complex number with the layout of `double _Complex`.
+ */
+typedef struct CComplex64 {
+  /**
+   * Real part
+   */
+  double re;
+  /**
+   * Imaginary part
+   */
+  double im;
+} CComplex64;
+
+/**
+ * Value of a [`CScalar`]. The field that is valid is determined by the dtype of the scalar.
+ */
+typedef union CScalarValue {
+  /**
+   * Float 32
+   */
+  float f32;
+  /**
+   * Float 64
+   */
+  double f64;
+  /**
+   * Complex 32
+   */
+  struct CComplex32 c32;
+  /**
+   * Complex 64
+   */
+  struct CComplex64 c64;
+  /**
+   * Unsigned int 8
+   */
+  uint8_t u8;
+  /**
+   * Unsigned int 32
+   */
+  uint32_t u32;
+  /**
+   * Unsigned int 64
+   */
+  uint64_t u64;
+  /**
+   * Int 8
+   */
+  int8_t i8;
+  /**
+   * Int 32
+   */
+  int32_t i32;
+  /**
+   * Int 64
+   */
+  int64_t i64;
+} CScalarValue;
+
+/**
+ * Scalar of any type that implements [`DTypeIdentifier`].
+ *
+ * Concretised functions can take and return scalars of a generic type as `CScalar`.
+ */
+typedef struct CScalar {
+  /**
+   * Type of the scalar as the discriminant of a [`DType`]. C can set any value, so it is
+   * only converted by [`CScalar::dtype`].
+   */
+  uint8_t dtype;
+  /**
+   * Value of the scalar.
+   */
+  union CScalarValue value;
+} CScalar;
+
 /**
  * Create a new instance of the wrapper.
+ *
+ * Available since version 0.1.0.
  */
 struct MyWrapper *my_wrapper_create(void);
 
 /**
  * Free the instance of the wrapper.
+ *
+ * Available since version 0.1.0.
  */
 void my_wrapper_free(struct MyWrapper *ptr);
 
+/**
+ * Make the wrapper depend on a `SharedWrapper`.
+ *
+ * The parent must be `refcounted`. It is kept alive until the wrapper is freed, even
+ * if the parent is freed first. Returns false and sets an error if one of the
+ * pointers is null.
+ *
+ * Available since version 0.1.0.
+ */
+bool my_wrapper_attach_shared_wrapper(struct MyWrapper *ptr, struct SharedWrapper *parent);
+
+/**
+ * Return the name of the type of the wrapped object.
+ *
+ * The returned string is valid until the end of the program. Returns a null
+ * pointer if the pointer is null. If the wrapper is borrowed mutably, an error is
+ * set and a null pointer is returned.
+ *
+ * Available since version 0.1.0.
+ */
+const char *my_wrapper_type_name(const struct MyWrapper *ptr);
+
+/**
+ * Return true if the wrapper does not hold an object or the pointer is null.
+ *
+ * If the wrapper is borrowed mutably, an error is set and true is returned.
+ *
+ * Available since version 0.1.0.
+ */
+bool my_wrapper_is_empty(const struct MyWrapper *ptr);
+
+/**
+ * Return true if both wrappers hold objects of the same type.
+ *
+ * Returns false if one of the pointers is null. If one of the wrappers is borrowed
+ * mutably, an error is set and false is returned.
+ *
+ * Available since version 0.1.0.
+ */
+bool my_wrapper_same_type(const struct MyWrapper *a, const struct MyWrapper *b);
+
+/**
+ * Create a new wrapper that holds a clone of the wrapped object.
+ *
+ * If the object cannot be cloned, an error is set and a null pointer is returned.
+ *
+ * Available since version 0.1.0.
+ */
+struct MyWrapper *my_wrapper_clone(const struct MyWrapper *ptr);
+
+/**
+ * Return the dtype of the wrapped object.
+ *
+ * Returns `Undefined` if the pointer is null or the wrapped object has no dtype.
+ * If the wrapper is borrowed mutably, an error is set and `Undefined` is returned.
+ *
+ * Available since version 0.1.0.
+ */
+DType my_wrapper_dtype(const struct MyWrapper *ptr);
+
+/**
+ * Create a new instance of the wrapper.
+ */
+struct SharedWrapper *shared_wrapper_create(void);
+
+/**
+ * Free the instance of the wrapper.
+ */
+void shared_wrapper_free(struct SharedWrapper *ptr);
+
+/**
+ * Add an owner to the wrapper and return the pointer.
+ *
+ * Every owner must call the release function once the wrapper is no longer needed.
+ */
+struct SharedWrapper *shared_wrapper_retain(struct SharedWrapper *ptr);
+
+/**
+ * Remove an owner from the wrapper. The wrapper is freed when it has no owners.
+ */
+void shared_wrapper_release(struct SharedWrapper *ptr);
+
+/**
+ * Return the number of owners of the wrapper, or 0 if the pointer is null.
+ */
+uintptr_t shared_wrapper_ref_count(const struct SharedWrapper *ptr);
+
+/**
+ * Create a new instance of the wrapper.
+ */
+struct SyncWrapper *sync_wrapper_create(void);
+
+/**
+ * Free the instance of the wrapper.
+ */
+void sync_wrapper_free(struct SyncWrapper *ptr);
+
+/**
+ * Return the name of the type of the wrapped object.
+ *
+ * The returned string is valid until the end of the program. Returns a null
+ * pointer if the pointer is null. If the wrapper is borrowed mutably, an error is
+ * set and a null pointer is returned.
+ */
+const char *sync_wrapper_type_name(const struct SyncWrapper *ptr);
+
+/**
+ * Create a new instance of the wrapper.
+ */
+struct MutexWrapper *mutex_wrapper_create(void);
+
+/**
+ * Free the instance of the wrapper.
+ */
+void mutex_wrapper_free(struct MutexWrapper *ptr);
+
+/**
+ * Create a new instance of the wrapper.
+ */
+struct CheckedWrapper *checked_wrapper_create(void);
+
+/**
+ * Free the instance of the wrapper.
+ */
+void checked_wrapper_free(struct CheckedWrapper *ptr);
+
+/**
+ * Return the name of the type of the wrapped object.
+ *
+ * The returned string is valid until the end of the program. Returns a null
+ * pointer if the pointer is null. If the wrapper is borrowed mutably, an error is
+ * set and a null pointer is returned.
+ */
+const char *checked_wrapper_type_name(const struct CheckedWrapper *ptr);
+
+/**
+ * Return true if the wrapper does not hold an object or the pointer is null.
+ *
+ * If the wrapper is borrowed mutably, an error is set and true is returned.
+ */
+bool checked_wrapper_is_empty(const struct CheckedWrapper *ptr);
+
+/**
+ * Return true if both wrappers hold objects of the same type.
+ *
+ * Returns false if one of the pointers is null. If one of the wrappers is borrowed
+ * mutably, an error is set and false is returned.
+ */
+bool checked_wrapper_same_type(const struct CheckedWrapper *a, const struct CheckedWrapper *b);
+
+/**
+ * Create a new wrapper that holds a clone of the wrapped object.
+ *
+ * If the object cannot be cloned, an error is set and a null pointer is returned.
+ */
+struct CheckedWrapper *checked_wrapper_clone(const struct CheckedWrapper *ptr);
+
+/**
+ * Return the dtype of the wrapped object.
+ *
+ * Returns `Undefined` if the pointer is null or the wrapped object has no dtype.
+ * If the wrapper is borrowed mutably, an error is set and `Undefined` is returned.
+ */
+DType checked_wrapper_dtype(const struct CheckedWrapper *ptr);
+
+/**
+ * Create a new instance of the wrapper.
+ */
+uint64_t table_wrapper_create(void);
+
+/**
+ * Free the instance of the wrapper. The handle is invalid afterwards.
+ */
+void table_wrapper_free(uint64_t ptr);
+
+/**
+ * Return the name of the type of the wrapped object.
+ *
+ * The returned string is valid until the end of the program. Returns a null
+ * pointer if the pointer is null. If the wrapper is borrowed mutably, an error is
+ * set and a null pointer is returned.
+ */
+const char *table_wrapper_type_name(uint64_t ptr);
+
+/**
+ * Create a new wrapped `MyStruct` with the given types.
+ */
+struct MyWrapper *my_struct_create(uint8_t dtype_a, uint8_t dtype_b, double a, double b);
+
+/**
+ * Test function.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f32, f64>`, `MyStruct<f64, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
 void test_func(struct MyWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `test_func` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *test_func_supported_types(uintptr_t index);
+
+/**
+ * Return true if `test_func` supports the types of the given arguments.
+ *
+ * This does not call `test_func` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool test_func_supports(struct MyWrapper *spam);
+
+/**
+ * Return the sum of both fields multiplied by a factor.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f32, f64>`, `MyStruct<f64, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double scaled_sum(struct MyWrapper *spam,
+                  double factor);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `scaled_sum`.
+ *
+ * Returns a function with the same arguments as `scaled_sum` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+double (*scaled_sum_resolve(struct MyWrapper *spam))(struct MyWrapper*, double);
+
+/**
+ * Apply `scaled_sum` to every wrapper in the array `spam` of length `spam_len`.
+ *
+ * The remaining arguments are passed unchanged to every call. If `status` is not null,
+ * the status code 0 is written to it for every element whose call succeeded and 1 for
+ * every element whose call failed.
+ * If `results` is not null, the value of each call is written to it.
+ * If a call fails, the error of the first element that failed is set.
+ * If `spam` is null and `spam_len` is not 0, an error is set and no call is made.
+ */
+void scaled_sum_batch(struct MyWrapper *const *spam,
+                      uintptr_t spam_len,
+                      double factor,
+                      double *results,
+                      int32_t *status);
+
+/**
+ * Return the combination of types with the given index that `scaled_sum` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *scaled_sum_supported_types(uintptr_t index);
+
+/**
+ * Return true if `scaled_sum` supports the types of the given arguments.
+ *
+ * This does not call `scaled_sum` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool scaled_sum_supports(struct MyWrapper *spam);
+
+/**
+ * Return the Euclidean norm of a vector with `dim` entries.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `vector` (`MyWrapper`): `MyVector<f32, 2>`, `MyVector<f32, 3>`, `MyVector<f64, 2>`, `MyVector<f64, 3>`
+ *
+ * The following arguments accept the given values:
+ * - `dim`: `2`, `3`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double norm(struct MyWrapper *vector,
+            uintptr_t dim);
+
+/**
+ * Return the combination of types with the given index that `norm` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *norm_supported_types(uintptr_t index);
+
+/**
+ * Return true if `norm` supports the types of the given arguments.
+ *
+ * This does not call `norm` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool norm_supports(struct MyWrapper *vector, uintptr_t dim);
+
+/**
+ * Reduce the fields of a struct.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * The following arguments accept the given values:
+ * - `_reduction`: `Reduction::Sum`, `Reduction::Product`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double reduce(struct MyWrapper *spam, int _reduction);
+
+/**
+ * Return the combination of types with the given index that `reduce` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *reduce_supported_types(uintptr_t index);
+
+/**
+ * Return true if `reduce` supports the types of the given arguments.
+ *
+ * This does not call `reduce` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool reduce_supports(struct MyWrapper *spam, int _reduction);
+
+/**
+ * Copy the values of one struct into another.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `target` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ * - `source` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * Only the combinations listed by `copy_values_supported_types` are supported.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void copy_values(struct MyWrapper *target, struct MyWrapper *source);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `copy_values`.
+ *
+ * Returns a function with the same arguments as `copy_values` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+void (*copy_values_resolve(struct MyWrapper *target, struct MyWrapper *source))(struct MyWrapper*,
+                                                                                struct MyWrapper*);
+
+/**
+ * Return the combination of types with the given index that `copy_values` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *copy_values_supported_types(uintptr_t index);
+
+/**
+ * Return true if `copy_values` supports the types of the given arguments.
+ *
+ * This does not call `copy_values` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool copy_values_supports(struct MyWrapper *target, struct MyWrapper *source);
+
+/**
+ * Move a struct into another one. The source wrapper is left empty.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `target` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ * - `source` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * Only the combinations listed by `move_values_supported_types` are supported.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void move_values(struct MyWrapper *target, struct MyWrapper *source);
+
+/**
+ * Return the combination of types with the given index that `move_values` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *move_values_supported_types(uintptr_t index);
+
+/**
+ * Return true if `move_values` supports the types of the given arguments.
+ *
+ * This does not call `move_values` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool move_values_supports(struct MyWrapper *target, struct MyWrapper *source);
+
+/**
+ * Sum the values of several structs.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `target` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ * - `sources` (array of `MyWrapper` with length `sources_len`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * Only the combinations listed by `sum_values_supported_types` are supported.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void sum_values(struct MyWrapper *target,
+                const struct MyWrapper *const *sources,
+                uintptr_t sources_len);
+
+/**
+ * Return the combination of types with the given index that `sum_values` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *sum_values_supported_types(uintptr_t index);
+
+/**
+ * Return true if `sum_values` supports the types of the given arguments.
+ *
+ * This does not call `sum_values` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool sum_values_supports(struct MyWrapper *target,
+                         const struct MyWrapper *const *sources,
+                         uintptr_t sources_len);
+
+/**
+ * Multiply both fields by a factor. Single precision structs are only supported if the
+ * `single-precision` feature is enabled.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>` (if `feature = "single-precision"`), `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void scale(struct MyWrapper *spam,
+           double factor);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `scale`.
+ *
+ * Returns a function with the same arguments as `scale` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+void (*scale_resolve(struct MyWrapper *spam))(struct MyWrapper*, double);
+
+/**
+ * Return the combination of types with the given index that `scale` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *scale_supported_types(uintptr_t index);
+
+/**
+ * Return true if `scale` supports the types of the given arguments.
+ *
+ * This does not call `scale` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool scale_supports(struct MyWrapper *spam);
+
+/**
+ * Return the mean of both fields. Single precision structs are converted to double precision.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f64, f64>`, `MyStruct<f32, f32>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double mean(struct MyWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `mean` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *mean_supported_types(uintptr_t index);
+
+/**
+ * Return true if `mean` supports the types of the given arguments.
+ *
+ * This does not call `mean` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool mean_supports(struct MyWrapper *spam);
+
+/**
+ * Return the sum of both fields.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ *
+ * Available since version 0.1.0.
+ */
+DEPRECATED("use `scaled_sum` with a factor of 1") double sum(struct MyWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `sum` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ *
+ * Available since version 0.1.0.
+ */
+DEPRECATED("use `scaled_sum` with a factor of 1") const char *sum_supported_types(uintptr_t index);
+
+/**
+ * Return true if `sum` supports the types of the given arguments.
+ *
+ * This does not call `sum` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ *
+ * Available since version 0.1.0.
+ */
+DEPRECATED("use `scaled_sum` with a factor of 1") bool sum_supports(struct MyWrapper *spam);
+
+/**
+ * Add a scalar to both fields.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * The scalar arguments `value` must have the dtype that the implementation expects.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void add_scalar(struct MyWrapper *spam, struct CScalar value);
+
+/**
+ * Return the combination of types with the given index that `add_scalar` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *add_scalar_supported_types(uintptr_t index);
+
+/**
+ * Return true if `add_scalar` supports the types of the given arguments.
+ *
+ * This does not call `add_scalar` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool add_scalar_supports(struct MyWrapper *spam);
+
+/**
+ * Return the sum of both fields multiplied by a scalar. The wrapper is freed.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * The scalar arguments `value` must have the dtype that the implementation expects.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double consume_scaled(struct MyWrapper *spam, struct CScalar value);
+
+/**
+ * Return the combination of types with the given index that `consume_scaled` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *consume_scaled_supported_types(uintptr_t index);
+
+/**
+ * Return true if `consume_scaled` supports the types of the given arguments.
+ *
+ * This does not call `consume_scaled` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool consume_scaled_supports(struct MyWrapper *spam);
+
+/**
+ * Return the first field.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`MyWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and a scalar with dtype `Undefined` is returned.
+ */
+struct CScalar first(struct MyWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `first` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *first_supported_types(uintptr_t index);
+
+/**
+ * Return true if `first` supports the types of the given arguments.
+ *
+ * This does not call `first` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool first_supports(struct MyWrapper *spam);
+
+/**
+ * Return the product of both fields of a shared struct.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`SharedWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double shared_product(struct SharedWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `shared_product` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *shared_product_supported_types(uintptr_t index);
+
+/**
+ * Return true if `shared_product` supports the types of the given arguments.
+ *
+ * This does not call `shared_product` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool shared_product_supports(struct SharedWrapper *spam);
+
+/**
+ * Return the sum of both fields of a shared struct. The wrapper is released.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`SharedWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double consume_shared(struct SharedWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `consume_shared` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *consume_shared_supported_types(uintptr_t index);
+
+/**
+ * Return true if `consume_shared` supports the types of the given arguments.
+ *
+ * This does not call `consume_shared` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool consume_shared_supports(struct SharedWrapper *spam);
+
+/**
+ * Increment the first field of a thread-safe struct.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`SyncWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void increment(struct SyncWrapper *spam);
+
+/**
+ * Apply `increment` to every wrapper in the array `spam` of length `spam_len`.
+ *
+ * The remaining arguments are passed unchanged to every call. If `status` is not null,
+ * the status code 0 is written to it for every element whose call succeeded and 1 for
+ * every element whose call failed.
+ * If a call fails, the error of the first element that failed is set.
+ * If `spam` is null and `spam_len` is not 0, an error is set and no call is made.
+ * The wrappers in the array must be distinct, otherwise an error is set and no call
+ * is made.
+ * If the `parallel` feature of `c-api-tools` is enabled, the calls run in parallel.
+ */
+void increment_batch(struct SyncWrapper *const *spam, uintptr_t spam_len, int32_t *status);
+
+/**
+ * Return the combination of types with the given index that `increment` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *increment_supported_types(uintptr_t index);
+
+/**
+ * Return true if `increment` supports the types of the given arguments.
+ *
+ * This does not call `increment` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool increment_supports(struct SyncWrapper *spam);
+
+/**
+ * Return the dot product of the fields of two thread-safe structs.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `first` (`MutexWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ * - `second` (`MutexWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * Only the combinations listed by `dot_supported_types` are supported.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double dot(struct MutexWrapper *first, struct MutexWrapper *second);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `dot`.
+ *
+ * Returns a function with the same arguments as `dot` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+double (*dot_resolve(struct MutexWrapper *first,
+                     struct MutexWrapper *second))(struct MutexWrapper*, struct MutexWrapper*);
+
+/**
+ * Return the combination of types with the given index that `dot` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *dot_supported_types(uintptr_t index);
+
+/**
+ * Return true if `dot` supports the types of the given arguments.
+ *
+ * This does not call `dot` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool dot_supports(struct MutexWrapper *first, struct MutexWrapper *second);
+
+/**
+ * Return the sum of the first fields of thread-safe structs.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spams` (array of `MutexWrapper` with length `spams_len`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double mutex_total(const struct MutexWrapper *const *spams,
+                   uintptr_t spams_len);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `mutex_total`.
+ *
+ * Returns a function with the same arguments as `mutex_total` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+double (*mutex_total_resolve(const struct MutexWrapper *const *spams,
+                             uintptr_t spams_len))(const struct MutexWrapper*const *, uintptr_t);
+
+/**
+ * Return the combination of types with the given index that `mutex_total` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *mutex_total_supported_types(uintptr_t index);
+
+/**
+ * Return true if `mutex_total` supports the types of the given arguments.
+ *
+ * This does not call `mutex_total` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool mutex_total_supports(const struct MutexWrapper *const *spams, uintptr_t spams_len);
+
+/**
+ * Return the difference of the sums of the first fields of two groups of thread-safe structs.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `first` (array of `MutexWrapper` with length `first_len`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ * - `second` (array of `MutexWrapper` with length `second_len`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * Only the combinations listed by `mutex_difference_supported_types` are supported.
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double mutex_difference(const struct MutexWrapper *const *first,
+                        uintptr_t first_len,
+                        const struct MutexWrapper *const *second,
+                        uintptr_t second_len);
+
+/**
+ * Return the combination of types with the given index that `mutex_difference` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *mutex_difference_supported_types(uintptr_t index);
+
+/**
+ * Return true if `mutex_difference` supports the types of the given arguments.
+ *
+ * This does not call `mutex_difference` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool mutex_difference_supports(const struct MutexWrapper *const *first,
+                               uintptr_t first_len,
+                               const struct MutexWrapper *const *second,
+                               uintptr_t second_len);
+
+/**
+ * Return the sum of both fields of a struct with borrow checks.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`CheckedWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double checked_sum(struct CheckedWrapper *spam);
+
+/**
+ * Return the combination of types with the given index that `checked_sum` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *checked_sum_supported_types(uintptr_t index);
+
+/**
+ * Return true if `checked_sum` supports the types of the given arguments.
+ *
+ * This does not call `checked_sum` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool checked_sum_supports(struct CheckedWrapper *spam);
+
+/**
+ * Add the value returned by `callback` to the first field of a struct with borrow checks.
+ * The struct is borrowed mutably while `callback` is called with `data`.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`CheckedWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error`.
+ */
+void update(struct CheckedWrapper *spam, double (*callback)(void*), void *data);
+
+/**
+ * Return the combination of types with the given index that `update` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *update_supported_types(uintptr_t index);
+
+/**
+ * Return true if `update` supports the types of the given arguments.
+ *
+ * This does not call `update` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool update_supports(struct CheckedWrapper *spam);
+
+/**
+ * Create a new `MyStruct` that is passed to C as a handle.
+ */
+uint64_t table_struct_create(uint8_t dtype, double a, double b);
+
+/**
+ * Return the difference of both fields of a struct passed as a handle.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`TableWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double difference(uint64_t spam);
+
+/**
+ * Resolve the concrete types of the wrapped objects passed to `difference`.
+ *
+ * Returns a function with the same arguments as `difference` that calls the implementation
+ * for these types without dispatching on them. The returned function must only be
+ * called with wrappers that hold objects of the same types. If the types are not
+ * supported, an error is set and a null pointer is returned.
+ */
+double (*difference_resolve(uint64_t spam))(uint64_t);
+
+/**
+ * Return the combination of types with the given index that `difference` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *difference_supported_types(uintptr_t index);
+
+/**
+ * Return true if `difference` supports the types of the given arguments.
+ *
+ * This does not call `difference` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool difference_supports(uint64_t spam);
+
+/**
+ * Return the sum of the first fields of structs passed as handles.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spams` (array of `TableWrapper` with length `spams_len`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double total(const uint64_t *spams,
+             uintptr_t spams_len);
+
+/**
+ * Return the combination of types with the given index that `total` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *total_supported_types(uintptr_t index);
+
+/**
+ * Return true if `total` supports the types of the given arguments.
+ *
+ * This does not call `total` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool total_supports(const uint64_t *spams, uintptr_t spams_len);
+
+/**
+ * Return the sum of both fields of a struct passed as a handle. The handle is freed.
+ *
+ * The wrapper arguments accept objects of the following types:
+ * - `spam` (`TableWrapper`): `MyStruct<f32, f32>`, `MyStruct<f64, f64>`
+ *
+ * If an argument is not supported, an error is set that can be retrieved with
+ * `c_api_tools_last_error` and NaN is returned.
+ */
+double consume_handle(uint64_t spam);
+
+/**
+ * Return the combination of types with the given index that `consume_handle` supports.
+ *
+ * A combination lists the types of the wrapped objects of all wrapper arguments,
+ * separated by commas. Returns a null pointer if the index is out of range.
+ */
+const char *consume_handle_supported_types(uintptr_t index);
+
+/**
+ * Return true if `consume_handle` supports the types of the given arguments.
+ *
+ * This does not call `consume_handle` and does not set an error. Returns false if
+ * a wrapper argument is an invalid handle.
+ */
+bool consume_handle_supports(uint64_t spam);
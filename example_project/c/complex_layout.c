@@ -0,0 +1,34 @@
+/* Check that the complex types in bindings.h have the layout of the C99 complex types. */
+#include <complex.h>
+#include <stddef.h>
+#include <string.h>
+
+#include "bindings.h"
+
+_Static_assert(sizeof(CComplex32) == sizeof(float _Complex), "size of CComplex32");
+_Static_assert(_Alignof(CComplex32) == _Alignof(float _Complex), "alignment of CComplex32");
+_Static_assert(offsetof(CComplex32, re) == 0, "offset of CComplex32.re");
+_Static_assert(offsetof(CComplex32, im) == sizeof(float), "offset of CComplex32.im");
+
+_Static_assert(sizeof(CComplex64) == sizeof(double _Complex), "size of CComplex64");
+_Static_assert(_Alignof(CComplex64) == _Alignof(double _Complex), "alignment of CComplex64");
+_Static_assert(offsetof(CComplex64, re) == 0, "offset of CComplex64.re");
+_Static_assert(offsetof(CComplex64, im) == sizeof(double), "offset of CComplex64.im");
+
+int main(void) {
+  float _Complex a = 1.0f + 2.0f * I;
+  CComplex32 b;
+  memcpy(&b, &a, sizeof(b));
+  if (b.re != crealf(a) || b.im != cimagf(a)) {
+    return 1;
+  }
+
+  double _Complex c = 3.0 + 4.0 * I;
+  CComplex64 d;
+  memcpy(&d, &c, sizeof(d));
+  if (d.re != creal(c) || d.im != cimag(c)) {
+    return 1;
+  }
+
+  return 0;
+}
@@ -5,13 +5,84 @@
 use std::fmt::Display;
 
 pub use c_api_tools::cfuncs;
+pub use c_api_tools::concretise_constructor;
 pub use c_api_tools::concretise_types;
+use c_api_tools::{DType, DTypeIdentifier, HasDType};
 
-#[cfuncs(name = "my_wrapper", create, free, unwrap)]
+#[cfuncs(
+    name = "my_wrapper",
+    create,
+    free,
+    unwrap,
+    type_name,
+    is_empty,
+    same_type,
+    validate,
+    depends_on = ["SharedWrapper"],
+    clone = [
+        "MyStruct<f32, f32>",
+        "MyStruct<f32, f64>",
+        "MyStruct<f64, f32>",
+        "MyStruct<f64, f64>"
+    ],
+    dtype = [
+        "MyStruct<f32, f32>",
+        "MyStruct<f32, f64>",
+        "MyStruct<f64, f32>",
+        "MyStruct<f64, f64>"
+    ],
+    since = "0.1.0"
+)]
 /// Wrapper for `MyStruct`.
 pub struct MyWrapper;
 
+#[cfuncs(name = "shared_wrapper", create, free, unwrap, refcounted)]
+/// Reference counted wrapper for `MyStruct`.
+pub struct SharedWrapper;
+
+#[cfuncs(
+    name = "sync_wrapper",
+    create,
+    free,
+    unwrap,
+    type_name,
+    sync = "rwlock"
+)]
+/// Thread-safe wrapper for `MyStruct` that is locked by a read-write lock.
+pub struct SyncWrapper;
+
+#[cfuncs(name = "mutex_wrapper", create, free, unwrap, sync = "mutex")]
+/// Thread-safe wrapper for `MyStruct` that is locked by a mutex.
+pub struct MutexWrapper;
+
+#[cfuncs(
+    name = "checked_wrapper",
+    create,
+    free,
+    unwrap,
+    type_name,
+    is_empty,
+    same_type,
+    borrow_check,
+    clone = ["MyStruct<f64, f64>"],
+    dtype = ["MyStruct<f64, f64>"]
+)]
+/// Wrapper for `MyStruct` that rejects conflicting borrows at runtime.
+pub struct CheckedWrapper;
+
+#[cfuncs(
+    name = "table_wrapper",
+    create,
+    free,
+    unwrap,
+    type_name,
+    handles = "table"
+)]
+/// Wrapper for `MyStruct` that is passed to C as an integer handle.
+pub struct TableWrapper;
+
 /// Some struct
+#[derive(Clone)]
 pub struct MyStruct<T: num::Float, V: num::Float> {
     /// First field.
     pub a: T,
@@ -26,6 +97,33 @@ impl<T: num::Float, V: num::Float> MyStruct<T, V> {
     }
 }
 
+impl<T: num::Float + DTypeIdentifier, V: num::Float> HasDType for MyStruct<T, V> {
+    type Scalar = T;
+}
+
+impl From<&MyStruct<f32, f32>> for MyStruct<f64, f64> {
+    fn from(value: &MyStruct<f32, f32>) -> Self {
+        Self::new(value.a as f64, value.b as f64)
+    }
+}
+
+#[concretise_constructor(
+    wrapper = "MyWrapper",
+    gen_type(name = "T", arg = 0, replace_with = ["f32", "f64"]),
+    gen_type(name = "V", arg = 1, replace_with = ["f32", "f64"]),
+)]
+/// Create a new wrapped `MyStruct` with the given types.
+pub fn my_struct_create<T: num::Float + DTypeIdentifier, V: num::Float + DTypeIdentifier>(
+    dtype_a: DType,
+    dtype_b: DType,
+    a: f64,
+    b: f64,
+) -> MyStruct<T, V> {
+    assert_eq!(dtype_a, T::dtype());
+    assert_eq!(dtype_b, V::dtype());
+    MyStruct::new(T::from(a).unwrap(), V::from(b).unwrap())
+}
+
 #[concretise_types(
     gen_type(name = "dtype1", replace_with = ["f32", "f64"]),
     gen_type(name = "dtype2", replace_with = ["f32", "f64"]),
@@ -36,6 +134,314 @@ pub fn test_func<T: num::Float + Display, V: num::Float + Display>(spam: &MyStru
     println!("{} {}", spam.a, spam.b);
 }
 
+#[concretise_types(
+    gen_type(name = "dtype1", replace_with = ["f32", "f64"]),
+    gen_type(name = "dtype2", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype1}}, {{dtype2}}>"]),
+    resolve,
+    batched,
+)]
+/// Return the sum of both fields multiplied by a factor.
+pub fn scaled_sum<T: num::Float, V: num::Float>(spam: &MyStruct<T, V>, factor: f64) -> f64 {
+    factor * (spam.a.to_f64().unwrap() + spam.b.to_f64().unwrap())
+}
+
+/// Vector with a fixed number of entries.
+pub struct MyVector<T: num::Float, const D: usize> {
+    /// Entries.
+    pub data: [T; D],
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    gen_const(name = "D", arg = 1, replace_with = [2, 3]),
+    field(arg = 0, name = "vec", wrapper = "MyWrapper", replace_with = ["MyVector<{{dtype}}, {{D}}>"]),
+)]
+/// Return the Euclidean norm of a vector with `dim` entries.
+pub fn norm<T: num::Float, const D: usize>(vector: &MyVector<T, D>, dim: usize) -> f64 {
+    assert_eq!(dim, D);
+    vector
+        .data
+        .iter()
+        .fold(T::zero(), |acc, x| acc + *x * *x)
+        .sqrt()
+        .to_f64()
+        .unwrap()
+}
+
+/// Reduction of the fields of a struct.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub enum Reduction {
+    /// Sum of the fields.
+    Sum,
+    /// Product of the fields.
+    Product,
+    /// Maximum of the fields.
+    Maximum,
+}
+
+/// Compile time implementation of a reduction.
+pub trait ReductionOp {
+    /// Reduce two values.
+    fn reduce<T: num::Float>(a: T, b: T) -> T;
+}
+
+/// Sum reduction.
+pub struct SumOp;
+
+impl ReductionOp for SumOp {
+    fn reduce<T: num::Float>(a: T, b: T) -> T {
+        a + b
+    }
+}
+
+/// Product reduction.
+pub struct ProductOp;
+
+impl ReductionOp for ProductOp {
+    fn reduce<T: num::Float>(a: T, b: T) -> T {
+        a * b
+    }
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    gen_enum(name = "R", arg = 1, replace_with = ["Reduction::Sum => SumOp", "Reduction::Product => ProductOp"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Reduce the fields of a struct.
+pub fn reduce<T: num::Float, R: ReductionOp>(spam: &MyStruct<T, T>, _reduction: Reduction) -> f64 {
+    R::reduce(spam.a, spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "target", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    field(arg = 1, name = "source", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    resolve,
+)]
+/// Copy the values of one struct into another.
+pub fn copy_values<T: num::Float>(target: &mut MyStruct<T, T>, source: &MyStruct<T, T>) {
+    target.a = source.a;
+    target.b = source.b;
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "target", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    field(arg = 1, name = "source", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Move a struct into another one. The source wrapper is left empty.
+pub fn move_values<T: num::Float>(target: &mut MyStruct<T, T>, source: MyStruct<T, T>) {
+    *target = source;
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "target", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    field(arg = 1, name = "sources", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Sum the values of several structs.
+pub fn sum_values<T: num::Float>(target: &mut MyStruct<T, T>, sources: &[&MyStruct<T, T>]) {
+    target.a = sources.iter().fold(T::zero(), |acc, s| acc + s.a);
+    target.b = sources.iter().fold(T::zero(), |acc, s| acc + s.b);
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = [cfg(feature = "single-precision", "f32"), "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    resolve,
+)]
+/// Multiply both fields by a factor. Single precision structs are only supported if the
+/// `single-precision` feature is enabled.
+pub fn scale<T: num::Float>(spam: &mut MyStruct<T, T>, factor: f64) {
+    spam.a = spam.a * T::from(factor).unwrap();
+    spam.b = spam.b * T::from(factor).unwrap();
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f64"], coerce = ["f32 -> f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the mean of both fields. Single precision structs are converted to double precision.
+pub fn mean(spam: &MyStruct<f64, f64>) -> f64 {
+    0.5 * (spam.a + spam.b)
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    since = "0.1.0",
+    deprecated = "use `scaled_sum` with a factor of 1",
+)]
+/// Return the sum of both fields.
+pub fn sum<T: num::Float>(spam: &MyStruct<T, T>) -> f64 {
+    (spam.a + spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    scalar(arg = 1),
+)]
+/// Add a scalar to both fields.
+pub fn add_scalar<T: num::Float>(spam: &mut MyStruct<T, T>, value: T) {
+    spam.a = spam.a + value;
+    spam.b = spam.b + value;
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", free, replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    scalar(arg = 1),
+)]
+/// Return the sum of both fields multiplied by a scalar. The wrapper is freed.
+pub fn consume_scaled<T: num::Float>(spam: MyStruct<T, T>, value: T) -> f64 {
+    ((spam.a + spam.b) * value).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MyWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    return_scalar,
+)]
+/// Return the first field.
+pub fn first<T: num::Float>(spam: &MyStruct<T, T>) -> T {
+    spam.a
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "SharedWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the product of both fields of a shared struct.
+pub fn shared_product<T: num::Float>(spam: &MyStruct<T, T>) -> f64 {
+    (spam.a * spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "SharedWrapper", free, replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the sum of both fields of a shared struct. The wrapper is released.
+pub fn consume_shared<T: num::Float>(spam: MyStruct<T, T>) -> f64 {
+    (spam.a + spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "SyncWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    batched = "parallel",
+)]
+/// Increment the first field of a thread-safe struct.
+pub fn increment<T: num::Float>(spam: &mut MyStruct<T, T>) {
+    spam.a = spam.a + T::one();
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "first", wrapper = "MutexWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    field(arg = 1, name = "second", wrapper = "MutexWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    resolve,
+)]
+/// Return the dot product of the fields of two thread-safe structs.
+pub fn dot<T: num::Float>(first: &MyStruct<T, T>, second: &MyStruct<T, T>) -> f64 {
+    (first.a * second.a + first.b * second.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "MutexWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    resolve,
+)]
+/// Return the sum of the first fields of thread-safe structs.
+pub fn mutex_total<T: num::Float>(spams: &[&MyStruct<T, T>]) -> f64 {
+    spams.iter().map(|spam| spam.a.to_f64().unwrap()).sum()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "first", wrapper = "MutexWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    field(arg = 1, name = "second", wrapper = "MutexWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the difference of the sums of the first fields of two groups of thread-safe structs.
+pub fn mutex_difference<T: num::Float>(
+    first: &[&MyStruct<T, T>],
+    second: &[&MyStruct<T, T>],
+) -> f64 {
+    let sum = |spams: &[&MyStruct<T, T>]| -> f64 {
+        spams.iter().map(|spam| spam.a.to_f64().unwrap()).sum()
+    };
+    sum(first) - sum(second)
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "CheckedWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the sum of both fields of a struct with borrow checks.
+pub fn checked_sum<T: num::Float>(spam: &MyStruct<T, T>) -> f64 {
+    (spam.a + spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "CheckedWrapper", replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Add the value returned by `callback` to the first field of a struct with borrow checks.
+/// The struct is borrowed mutably while `callback` is called with `data`.
+pub fn update<T: num::Float>(
+    spam: &mut MyStruct<T, T>,
+    callback: extern "C" fn(*mut std::ffi::c_void) -> f64,
+    data: *mut std::ffi::c_void,
+) {
+    spam.a = spam.a + T::from(callback(data)).unwrap();
+}
+
+#[concretise_constructor(
+    wrapper = "TableWrapper",
+    gen_type(name = "T", arg = 0, replace_with = ["f32", "f64"]),
+    handle,
+)]
+/// Create a new `MyStruct` that is passed to C as a handle.
+pub fn table_struct_create<T: num::Float + DTypeIdentifier>(
+    dtype: DType,
+    a: f64,
+    b: f64,
+) -> MyStruct<T, T> {
+    assert_eq!(dtype, T::dtype());
+    MyStruct::new(T::from(a).unwrap(), T::from(b).unwrap())
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "TableWrapper", handle, replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+    resolve,
+)]
+/// Return the difference of both fields of a struct passed as a handle.
+pub fn difference<T: num::Float>(spam: &MyStruct<T, T>) -> f64 {
+    (spam.a - spam.b).to_f64().unwrap()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "TableWrapper", handle, replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the sum of the first fields of structs passed as handles.
+pub fn total<T: num::Float>(spams: &[&MyStruct<T, T>]) -> f64 {
+    spams.iter().map(|spam| spam.a.to_f64().unwrap()).sum()
+}
+
+#[concretise_types(
+    gen_type(name = "dtype", replace_with = ["f32", "f64"]),
+    field(arg = 0, name = "wrap", wrapper = "TableWrapper", handle, free, replace_with = ["MyStruct<{{dtype}}, {{dtype}}>"]),
+)]
+/// Return the sum of both fields of a struct passed as a handle. The handle is freed.
+pub fn consume_handle<T: num::Float>(spam: MyStruct<T, T>) -> f64 {
+    (spam.a + spam.b).to_f64().unwrap()
+}
+
 #[cfg(test)]
 mod test {
 
@@ -52,4 +458,900 @@ mod test {
         unsafe { test_func(wrapper) };
         unsafe { my_wrapper_free(wrapper) };
     }
+
+    #[test]
+    fn test_constructor() {
+        let wrapper = my_struct_create(DType::F32 as u8, DType::F64 as u8, 1.0, 2.0);
+
+        let obj = unsafe { my_wrapper_unwrap(wrapper) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f32, f64>>()
+            .unwrap();
+        assert_eq!(obj.a, 1.0);
+        assert_eq!(obj.b, 2.0);
+
+        unsafe { my_wrapper_free(wrapper) };
+
+        assert!(my_struct_create(DType::F32 as u8, DType::I32 as u8, 1.0, 2.0).is_null());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("Unsupported dtypes"));
+
+        // Values that are not a dtype are rejected before they are converted.
+        assert!(my_struct_create(DType::F32 as u8, 200, 1.0, 2.0).is_null());
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Unsupported dtypes for `my_struct_create`: (F32, 200)."
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let wrapper = my_wrapper_create();
+
+        *unsafe { my_wrapper_unwrap(wrapper) }.unwrap() =
+            Box::new(MyStruct::<f32, f64> { a: 1.0, b: 2.0 });
+
+        let resolved = unsafe { scaled_sum_resolve(wrapper) }.unwrap();
+        for factor in [1.0, 2.0, 3.0] {
+            assert_eq!(unsafe { resolved(wrapper, factor) }, 3.0 * factor);
+        }
+
+        *unsafe { my_wrapper_unwrap(wrapper) }.unwrap() = Box::new(());
+        assert!(unsafe { scaled_sum_resolve(wrapper) }.is_none());
+        assert!(c_api_tools::take_last_error().is_some());
+
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_enum_dispatch() {
+        use std::ffi::c_int;
+
+        let wrapper = my_struct_create(DType::F64 as u8, DType::F64 as u8, 2.0, 3.0);
+
+        assert_eq!(unsafe { reduce(wrapper, Reduction::Sum as c_int) }, 5.0);
+        assert_eq!(unsafe { reduce(wrapper, Reduction::Product as c_int) }, 6.0);
+        assert!(c_api_tools::take_last_error().is_none());
+
+        assert!(unsafe { reduce(wrapper, Reduction::Maximum as c_int) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("Unsupported value of `_reduction`"));
+
+        // Values that are not a variant of the enum are rejected before they are converted.
+        assert!(unsafe { reduce(wrapper, 7) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("Unsupported value of `_reduction`"));
+        assert!(!unsafe { reduce_supports(wrapper, 7) });
+        assert!(c_api_tools::take_last_error().is_none());
+
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_const_dispatch() {
+        let wrapper = Box::into_raw(Box::new(MyWrapper::new(MyVector::<f32, 2> {
+            data: [3.0, 4.0],
+        })));
+
+        assert_eq!(unsafe { norm(wrapper, 2) }, 5.0);
+        assert!(c_api_tools::take_last_error().is_none());
+
+        assert!(unsafe { norm(wrapper, 4) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("Unsupported value of `dim`"));
+
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_batch() {
+        let wrappers = [
+            my_wrapper_create(),
+            my_wrapper_create(),
+            my_wrapper_create(),
+        ];
+
+        *unsafe { my_wrapper_unwrap(wrappers[0]) }.unwrap() =
+            Box::new(MyStruct::<f32, f64> { a: 1.0, b: 2.0 });
+        *unsafe { my_wrapper_unwrap(wrappers[2]) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 3.0, b: 4.0 });
+
+        let mut results = [0.0; 3];
+        let mut status = [-1; 3];
+        unsafe {
+            scaled_sum_batch(
+                wrappers.as_ptr(),
+                wrappers.len(),
+                2.0,
+                results.as_mut_ptr(),
+                status.as_mut_ptr(),
+            )
+        };
+
+        assert_eq!(status, [0, 1, 0]);
+        assert_eq!(results[0], 6.0);
+        assert_eq!(results[2], 14.0);
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .starts_with("Element 1: "));
+
+        for wrapper in wrappers {
+            unsafe { my_wrapper_free(wrapper) };
+        }
+    }
+
+    #[test]
+    fn test_aliased_arguments() {
+        let target = my_wrapper_create();
+        let source = my_wrapper_create();
+
+        *unsafe { my_wrapper_unwrap(target) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 0.0, b: 0.0 });
+        *unsafe { my_wrapper_unwrap(source) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 2.0 });
+
+        unsafe { copy_values(target, source) };
+        assert!(c_api_tools::take_last_error().is_none());
+
+        let copied = unsafe { my_wrapper_unwrap(target) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f64, f64>>()
+            .unwrap();
+        assert_eq!(copied.a, 1.0);
+        assert_eq!(copied.b, 2.0);
+
+        unsafe { copy_values(target, target) };
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("point to the same wrapper"));
+
+        // Resolved functions check their arguments again.
+        assert!(unsafe { copy_values_resolve(target, target) }.is_none());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("point to the same wrapper"));
+        let resolved = unsafe { copy_values_resolve(target, source) }.unwrap();
+        unsafe { resolved(target, target) };
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("point to the same wrapper"));
+        unsafe { resolved(std::ptr::null_mut(), source) };
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+
+        unsafe { my_wrapper_free(target) };
+        unsafe { my_wrapper_free(source) };
+    }
+
+    #[test]
+    fn test_consumed_argument() {
+        let target = my_wrapper_create();
+        let source = my_wrapper_create();
+
+        *unsafe { my_wrapper_unwrap(target) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 0.0, b: 0.0 });
+        *unsafe { my_wrapper_unwrap(source) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 1.0, b: 2.0 });
+
+        unsafe { move_values(target, source) };
+
+        let moved = unsafe { my_wrapper_unwrap(target) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f32, f32>>()
+            .unwrap();
+        assert_eq!(moved.a, 1.0);
+        assert_eq!(moved.b, 2.0);
+
+        assert!(unsafe { my_wrapper_unwrap(source) }.unwrap().is::<()>());
+
+        unsafe { my_wrapper_free(target) };
+        unsafe { my_wrapper_free(source) };
+
+        // A consumed argument with `free` releases its wrapper.
+        let shared = shared_wrapper_create();
+        *unsafe { shared_wrapper_unwrap(shared) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 2.0 });
+        let other = unsafe { shared_wrapper_retain(shared) };
+        assert_eq!(unsafe { consume_shared(shared) }, 3.0);
+        assert!(c_api_tools::take_last_error().is_none());
+        assert_eq!(unsafe { shared_wrapper_ref_count(other) }, 1);
+        assert!(unsafe { shared_wrapper_unwrap(other) }.unwrap().is::<()>());
+        unsafe { shared_wrapper_free(other) };
+
+        // A consumed argument is only taken if the call succeeds.
+        let wrapper = my_struct_create(DType::F32 as u8, DType::F32 as u8, 1.0, 2.0);
+        assert!(unsafe { consume_scaled(wrapper, c_api_tools::CScalar::from(2.0f64)) }.is_nan());
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Argument `value` has dtype F64, expected F32."
+        );
+        assert_eq!(f32::try_from(unsafe { first(wrapper) }), Ok(1.0));
+        assert_eq!(
+            unsafe { consume_scaled(wrapper, c_api_tools::CScalar::from(2.0f32)) },
+            6.0
+        );
+        assert!(c_api_tools::take_last_error().is_none());
+
+        // A consumed handle with `free` is removed from the table.
+        let handle = table_struct_create(DType::F32 as u8, 3.0, 4.0);
+        assert_eq!(unsafe { consume_handle(handle) }, 7.0);
+        assert!(c_api_tools::take_last_error().is_none());
+        assert!(TableWrapper::pin_handle(handle).is_none());
+        assert!(unsafe { consume_handle(handle) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+    }
+
+    #[test]
+    fn test_slice_argument() {
+        let target = my_wrapper_create();
+        let sources = [my_wrapper_create(), my_wrapper_create()];
+
+        *unsafe { my_wrapper_unwrap(target) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 0.0, b: 0.0 });
+        *unsafe { my_wrapper_unwrap(sources[0]) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 2.0 });
+        *unsafe { my_wrapper_unwrap(sources[1]) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 3.0, b: 4.0 });
+
+        let source_ptrs = sources.map(|source| source as *const MyWrapper);
+        unsafe { sum_values(target, source_ptrs.as_ptr(), source_ptrs.len()) };
+        assert!(c_api_tools::take_last_error().is_none());
+
+        let sum = unsafe { my_wrapper_unwrap(target) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f64, f64>>()
+            .unwrap();
+        assert_eq!(sum.a, 4.0);
+        assert_eq!(sum.b, 6.0);
+
+        *unsafe { my_wrapper_unwrap(sources[1]) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 3.0, b: 4.0 });
+        unsafe { sum_values(target, source_ptrs.as_ptr(), source_ptrs.len()) };
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("different types"));
+
+        unsafe { my_wrapper_free(target) };
+        for source in sources {
+            unsafe { my_wrapper_free(source) };
+        }
+    }
+
+    #[test]
+    fn test_conditional_types() {
+        let double = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        let single = my_struct_create(DType::F32 as u8, DType::F32 as u8, 1.0, 2.0);
+
+        unsafe { scale(double, 2.0) };
+        let scaled = unsafe { my_wrapper_unwrap(double) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f64, f64>>()
+            .unwrap();
+        assert_eq!(scaled.a, 2.0);
+        assert_eq!(scaled.b, 4.0);
+
+        assert_eq!(
+            unsafe { scale_resolve(single) }.is_some(),
+            cfg!(feature = "single-precision")
+        );
+        assert_eq!(
+            c_api_tools::take_last_error().is_none(),
+            cfg!(feature = "single-precision")
+        );
+
+        unsafe { scale(single, 2.0) };
+        assert_eq!(
+            c_api_tools::take_last_error().is_none(),
+            cfg!(feature = "single-precision")
+        );
+
+        unsafe { my_wrapper_free(double) };
+        unsafe { my_wrapper_free(single) };
+    }
+
+    #[test]
+    fn test_coercion() {
+        let double = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        let single = my_struct_create(DType::F32 as u8, DType::F32 as u8, 3.0, 4.0);
+
+        assert_eq!(unsafe { mean(double) }, 1.5);
+        assert_eq!(unsafe { mean(single) }, 3.5);
+        assert!(c_api_tools::take_last_error().is_none());
+
+        unsafe { my_wrapper_free(double) };
+        unsafe { my_wrapper_free(single) };
+    }
+
+    #[test]
+    fn test_supported_types() {
+        let supported = (0..)
+            .map(|index| test_func_supported_types(index))
+            .take_while(|entry| !entry.is_null())
+            .map(|entry| {
+                unsafe { std::ffi::CStr::from_ptr(entry) }
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            supported,
+            [
+                "MyStruct<f32, f32>",
+                "MyStruct<f32, f64>",
+                "MyStruct<f64, f32>",
+                "MyStruct<f64, f64>"
+            ]
+        );
+
+        let wrapper = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        assert!(unsafe { reduce_supports(wrapper, Reduction::Sum as std::ffi::c_int) });
+        assert!(!unsafe { reduce_supports(wrapper, Reduction::Maximum as std::ffi::c_int) });
+        unsafe { my_wrapper_free(wrapper) };
+
+        let wrapper = my_wrapper_create();
+        assert!(!unsafe { test_func_supports(wrapper) });
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let wrapper = my_struct_create(DType::F32 as u8, DType::F64 as u8, 1.0, 2.0);
+        assert_eq!(
+            unsafe { my_wrapper_unwrap(wrapper) }.unwrap().type_name(),
+            "example_project::MyStruct<f32, f64>"
+        );
+
+        assert!(unsafe { reduce(wrapper, Reduction::Sum as std::ffi::c_int) }.is_nan());
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Argument `spam` holds `MyStruct<f32, f64>`, expected one of: MyStruct<f32, f32>, MyStruct<f64, f64>."
+        );
+        unsafe { my_wrapper_free(wrapper) };
+
+        let target = my_struct_create(DType::F32 as u8, DType::F32 as u8, 1.0, 2.0);
+        let source = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        unsafe { copy_values(target, source) };
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Unsupported combination of types: `target` holds `MyStruct<f32, f32>`, `source` holds `MyStruct<f64, f64>`."
+        );
+        unsafe { my_wrapper_free(target) };
+        unsafe { my_wrapper_free(source) };
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated() {
+        let wrapper = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        assert_eq!(unsafe { sum(wrapper) }, 3.0);
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_scalar_arguments() {
+        let wrapper = my_struct_create(DType::F32 as u8, DType::F32 as u8, 1.0, 2.0);
+
+        unsafe { add_scalar(wrapper, c_api_tools::CScalar::from(0.5f32)) };
+        assert!(c_api_tools::take_last_error().is_none());
+
+        let value = unsafe { first(wrapper) };
+        assert_eq!(value.dtype(), DType::F32);
+        assert_eq!(f32::try_from(value), Ok(1.5));
+
+        unsafe { add_scalar(wrapper, c_api_tools::CScalar::from(0.5f64)) };
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Argument `value` has dtype F64, expected F32."
+        );
+        assert_eq!(f32::try_from(unsafe { first(wrapper) }), Ok(1.5));
+
+        // A dtype that is set to an invalid value by C is undefined.
+        let invalid = c_api_tools::CScalar {
+            dtype: 200,
+            value: c_api_tools::CScalarValue { f32: 0.5 },
+        };
+        unsafe { add_scalar(wrapper, invalid) };
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Argument `value` has dtype Undefined, expected F32."
+        );
+
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_complex_layout() {
+        use c_api_tools::{CComplex32, CComplex64};
+
+        assert_eq!(
+            std::mem::size_of::<CComplex32>(),
+            2 * std::mem::size_of::<f32>()
+        );
+        assert_eq!(
+            std::mem::align_of::<CComplex32>(),
+            std::mem::align_of::<f32>()
+        );
+        assert_eq!(
+            std::mem::size_of::<CComplex64>(),
+            2 * std::mem::size_of::<f64>()
+        );
+        assert_eq!(
+            std::mem::align_of::<CComplex64>(),
+            std::mem::align_of::<f64>()
+        );
+
+        let value = num::complex::Complex::new(1.0f64, 2.0f64);
+        assert_eq!(num::complex::Complex::from(CComplex64::from(value)), value);
+
+        // Compile and run a C program that compares the layout with the C99 complex types.
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let executable =
+            std::env::temp_dir().join(format!("complex_layout_{}", std::process::id()));
+        let compiler = std::env::var("CC").unwrap_or("cc".to_string());
+        let status = std::process::Command::new(compiler)
+            .arg("-std=c11")
+            .arg("-I")
+            .arg(dir)
+            .arg(dir.join("c/complex_layout.c"))
+            .arg("-o")
+            .arg(&executable)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(std::process::Command::new(&executable)
+            .status()
+            .unwrap()
+            .success());
+        std::fs::remove_file(executable).unwrap();
+    }
+
+    #[test]
+    fn test_dtype() {
+        let wrapper = my_struct_create(DType::F32 as u8, DType::F64 as u8, 1.0, 2.0);
+        assert_eq!(unsafe { my_wrapper_dtype(wrapper) }, DType::F32);
+        assert_eq!(unsafe { &*wrapper }.dtype(), DType::F32);
+        unsafe { my_wrapper_free(wrapper) };
+
+        let wrapper = my_wrapper_create();
+        assert_eq!(unsafe { my_wrapper_dtype(wrapper) }, DType::Undefined);
+        unsafe { my_wrapper_free(wrapper) };
+
+        assert_eq!(
+            unsafe { my_wrapper_dtype(std::ptr::null()) },
+            DType::Undefined
+        );
+    }
+
+    #[test]
+    fn test_introspection() {
+        let a = my_struct_create(DType::F32 as u8, DType::F64 as u8, 1.0, 2.0);
+        let b = my_struct_create(DType::F32 as u8, DType::F64 as u8, 3.0, 4.0);
+        let c = my_wrapper_create();
+
+        let name = unsafe { std::ffi::CStr::from_ptr(my_wrapper_type_name(a)) };
+        assert_eq!(
+            name.to_str().unwrap(),
+            "example_project::MyStruct<f32, f64>"
+        );
+        assert_eq!(unsafe { my_wrapper_type_name(b) }, unsafe {
+            my_wrapper_type_name(a)
+        });
+
+        assert!(!unsafe { my_wrapper_is_empty(a) });
+        assert!(unsafe { my_wrapper_is_empty(c) });
+
+        assert!(unsafe { my_wrapper_same_type(a, b) });
+        assert!(!unsafe { my_wrapper_same_type(a, c) });
+        assert!(!unsafe { my_wrapper_same_type(a, std::ptr::null()) });
+
+        for wrapper in [a, b, c] {
+            unsafe { my_wrapper_free(wrapper) };
+        }
+    }
+
+    #[test]
+    fn test_clone() {
+        let wrapper = my_struct_create(DType::F64 as u8, DType::F32 as u8, 1.0, 2.0);
+        let clone = unsafe { my_wrapper_clone(wrapper) };
+        unsafe { my_wrapper_free(wrapper) };
+
+        let obj = unsafe { my_wrapper_unwrap(clone) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f64, f32>>()
+            .unwrap();
+        assert_eq!(obj.a, 1.0);
+        assert_eq!(obj.b, 2.0);
+        unsafe { my_wrapper_free(clone) };
+
+        let wrapper = my_wrapper_create();
+        let clone = unsafe { my_wrapper_clone(wrapper) };
+        assert!(unsafe { my_wrapper_is_empty(clone) });
+        unsafe { my_wrapper_free(clone) };
+
+        *unsafe { my_wrapper_unwrap(wrapper) }.unwrap() =
+            Box::new(MyVector::<f32, 2> { data: [1.0, 2.0] });
+        assert!(unsafe { my_wrapper_clone(wrapper) }.is_null());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be cloned"));
+        unsafe { my_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_refcounted() {
+        let wrapper = shared_wrapper_create();
+        *unsafe { shared_wrapper_unwrap(wrapper) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 2.0, b: 3.0 });
+        assert_eq!(unsafe { shared_wrapper_ref_count(wrapper) }, 1);
+
+        let other = unsafe { shared_wrapper_retain(wrapper) };
+        assert_eq!(other, wrapper);
+        assert_eq!(unsafe { shared_wrapper_ref_count(wrapper) }, 2);
+
+        unsafe { shared_wrapper_release(wrapper) };
+        assert_eq!(unsafe { shared_wrapper_ref_count(other) }, 1);
+        assert_eq!(unsafe { shared_product(other) }, 6.0);
+
+        assert_eq!(unsafe { shared_wrapper_ref_count(std::ptr::null()) }, 0);
+        assert!(unsafe { shared_wrapper_retain(std::ptr::null_mut()) }.is_null());
+
+        unsafe { shared_wrapper_free(other) };
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let parent = shared_wrapper_create();
+        *unsafe { shared_wrapper_unwrap(parent) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 4.0 });
+        let child = my_wrapper_create();
+
+        assert!(unsafe { my_wrapper_attach_shared_wrapper(child, parent) });
+        assert_eq!(unsafe { shared_wrapper_ref_count(parent) }, 2);
+
+        let clone = unsafe { my_wrapper_clone(child) };
+        assert_eq!(unsafe { shared_wrapper_ref_count(parent) }, 3);
+        unsafe { my_wrapper_free(clone) };
+
+        // The parent stays alive while the child depends on it.
+        unsafe { shared_wrapper_free(parent) };
+        let dependencies = unsafe { &*child }.dependencies();
+        let dependency = dependencies[0].downcast_ref::<SharedWrapper>().unwrap();
+        let obj = dependency
+            .inner()
+            .downcast_ref::<MyStruct<f64, f64>>()
+            .unwrap();
+        assert_eq!(obj.b, 4.0);
+
+        assert!(!unsafe { my_wrapper_attach_shared_wrapper(child, std::ptr::null_mut()) });
+        assert!(c_api_tools::take_last_error().is_some());
+
+        unsafe { my_wrapper_free(child) };
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncWrapper>();
+        assert_send_sync::<MutexWrapper>();
+        assert_send_sync::<CheckedWrapper>();
+        // Locking a read-write lock twice on one thread can deadlock.
+        assert!(!SyncWrapper::shared_reads());
+
+        let wrapper = sync_wrapper_create();
+        *unsafe { sync_wrapper_unwrap(wrapper) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 0.0, b: 0.0 });
+
+        // The wrapper is `Sync`, so a reference can be shared with the threads.
+        let shared = unsafe { &*wrapper };
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        unsafe { increment(shared as *const SyncWrapper as *mut SyncWrapper) };
+                    }
+                });
+            }
+        });
+
+        let obj = unsafe { sync_wrapper_unwrap(wrapper) }
+            .unwrap()
+            .downcast_ref::<MyStruct<f64, f64>>()
+            .unwrap();
+        assert_eq!(obj.a, 4000.0);
+        unsafe { sync_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_sync_batch() {
+        let wrappers = [sync_wrapper_create(), sync_wrapper_create()];
+        for wrapper in wrappers {
+            *unsafe { sync_wrapper_unwrap(wrapper) }.unwrap() =
+                Box::new(MyStruct::<f32, f32> { a: 1.0, b: 0.0 });
+        }
+
+        let mut status = [-1; 2];
+        unsafe { increment_batch(wrappers.as_ptr(), wrappers.len(), status.as_mut_ptr()) };
+        assert_eq!(status, [0, 0]);
+        assert!(c_api_tools::take_last_error().is_none());
+
+        // Mutable batches reject repeated wrappers.
+        let repeated = [wrappers[0], wrappers[0]];
+        unsafe { increment_batch(repeated.as_ptr(), repeated.len(), status.as_mut_ptr()) };
+        assert_eq!(status, [1, 1]);
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("point to the same wrapper"));
+
+        // The status codes are optional.
+        unsafe { increment_batch(wrappers.as_ptr(), wrappers.len(), std::ptr::null_mut()) };
+        assert!(c_api_tools::take_last_error().is_none());
+
+        // A null array is rejected unless it is empty.
+        unsafe { increment_batch(std::ptr::null(), 2, status.as_mut_ptr()) };
+        assert_eq!(status, [1, 1]);
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Argument `spam` is a null pointer."
+        );
+        unsafe { increment_batch(std::ptr::null(), 0, std::ptr::null_mut()) };
+        assert!(c_api_tools::take_last_error().is_none());
+
+        for wrapper in wrappers {
+            let obj = unsafe { sync_wrapper_unwrap(wrapper) }
+                .unwrap()
+                .downcast_ref::<MyStruct<f32, f32>>()
+                .unwrap();
+            assert_eq!(obj.a, 3.0);
+            unsafe { sync_wrapper_free(wrapper) };
+        }
+    }
+
+    #[test]
+    fn test_mutex() {
+        let first = mutex_wrapper_create();
+        let second = mutex_wrapper_create();
+        *unsafe { mutex_wrapper_unwrap(first) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 1.0, b: 2.0 });
+        *unsafe { mutex_wrapper_unwrap(second) }.unwrap() =
+            Box::new(MyStruct::<f32, f32> { a: 3.0, b: 4.0 });
+
+        assert_eq!(unsafe { dot(first, second) }, 11.0);
+
+        // Wrappers are locked in the order of their addresses, so a call that waits for a
+        // wrapper does not hold a wrapper with a higher address. The resolved function is
+        // used as it locks the wrappers only once.
+        let resolved = unsafe { dot_resolve(first, second) }.unwrap();
+        let (low, high) = if first < second {
+            (first as usize, second as usize)
+        } else {
+            (second as usize, first as usize)
+        };
+        let guard = unsafe { &*(low as *const MutexWrapper) }
+            .try_read()
+            .unwrap();
+        let waiting = std::thread::spawn(move || unsafe {
+            resolved(high as *mut MutexWrapper, low as *mut MutexWrapper)
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let high = [high as *const MutexWrapper];
+            sender
+                .send(unsafe { mutex_total(high.as_ptr(), high.len()) })
+                .unwrap();
+        });
+        assert!(receiver
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .is_ok());
+        drop(guard);
+        assert_eq!(waiting.join().unwrap(), 11.0);
+
+        // Locking the same mutex twice would deadlock.
+        assert!(unsafe { dot(first, first) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be locked twice"));
+        assert!(unsafe { dot_resolve(first, first) }.is_none());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be locked twice"));
+        let resolved = unsafe { dot_resolve(first, second) }.unwrap();
+        assert!(unsafe { resolved(first, first) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be locked twice"));
+
+        // The elements of a slice are checked by the resolved function as well.
+        let distinct = [first as *const MutexWrapper, second];
+        let resolved = unsafe { mutex_total_resolve(distinct.as_ptr(), distinct.len()) }.unwrap();
+        assert_eq!(unsafe { resolved(distinct.as_ptr(), distinct.len()) }, 4.0);
+        let repeated = [first as *const MutexWrapper, first];
+        assert!(unsafe { resolved(repeated.as_ptr(), repeated.len()) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be locked twice"));
+
+        // A wrapper that appears in two slices would be locked twice as well.
+        let firsts = [first as *const MutexWrapper];
+        let seconds = [second as *const MutexWrapper];
+        assert_eq!(
+            unsafe { mutex_difference(firsts.as_ptr(), 1, seconds.as_ptr(), 1) },
+            -2.0
+        );
+        assert!(unsafe { mutex_difference(firsts.as_ptr(), 1, firsts.as_ptr(), 1) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("cannot be locked twice"));
+
+        unsafe { mutex_wrapper_free(first) };
+        unsafe { mutex_wrapper_free(second) };
+    }
+
+    #[test]
+    fn test_borrow_check() {
+        // Calls `checked_sum` while `update` borrows the struct mutably. Returns 1 if the
+        // call fails with a borrow error.
+        extern "C" fn reenter(data: *mut std::ffi::c_void) -> f64 {
+            let result = unsafe { checked_sum(data as *mut CheckedWrapper) };
+            let error = c_api_tools::take_last_error().unwrap_or_default();
+            if result.is_nan() && error.contains("wrapper already borrowed") {
+                1.0
+            } else {
+                0.0
+            }
+        }
+
+        // Calls the introspection functions while `update` borrows the struct mutably. Returns
+        // 1 if all of them fail with a borrow error instead of panicking.
+        extern "C" fn introspect(data: *mut std::ffi::c_void) -> f64 {
+            let wrapper = data as *mut CheckedWrapper;
+            let borrowed = || {
+                c_api_tools::take_last_error()
+                    .is_some_and(|error| error.contains("wrapper already borrowed"))
+            };
+            let failed = unsafe { checked_wrapper_type_name(wrapper) }.is_null()
+                && borrowed()
+                && unsafe { checked_wrapper_is_empty(wrapper) }
+                && borrowed()
+                && !unsafe { checked_wrapper_same_type(wrapper, wrapper) }
+                && borrowed()
+                && unsafe { checked_wrapper_clone(wrapper) }.is_null()
+                && borrowed()
+                && unsafe { checked_wrapper_dtype(wrapper) } == DType::Undefined
+                && borrowed();
+            if failed {
+                1.0
+            } else {
+                0.0
+            }
+        }
+
+        let wrapper = checked_wrapper_create();
+        *unsafe { checked_wrapper_unwrap(wrapper) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 2.0 });
+
+        unsafe { update(wrapper, reenter, wrapper as *mut std::ffi::c_void) };
+        assert_eq!(unsafe { checked_sum(wrapper) }, 4.0);
+        unsafe { update(wrapper, introspect, wrapper as *mut std::ffi::c_void) };
+        assert_eq!(unsafe { checked_sum(wrapper) }, 5.0);
+        assert_eq!(unsafe { checked_wrapper_dtype(wrapper) }, DType::F64);
+
+        unsafe { checked_wrapper_free(wrapper) };
+    }
+
+    #[test]
+    fn test_validate() {
+        let wrapper = my_struct_create(DType::F64 as u8, DType::F64 as u8, 1.0, 2.0);
+        assert_eq!(unsafe { scaled_sum(wrapper, 1.0) }, 3.0);
+        let resolved = unsafe { scaled_sum_resolve(wrapper) }.unwrap();
+
+        // A pointer to a wrapper of another type is rejected.
+        let other = shared_wrapper_create();
+        assert!(unsafe { scaled_sum(other as *mut MyWrapper, 1.0) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+        assert!(!unsafe { scaled_sum_supports(other as *mut MyWrapper) });
+        assert!(unsafe { my_wrapper_type_name(other as *const MyWrapper) }.is_null());
+        assert!(c_api_tools::take_last_error().is_some());
+        unsafe { shared_wrapper_free(other) };
+
+        assert!(unsafe { scaled_sum(std::ptr::null_mut(), 1.0) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+
+        // Drop the wrapper without releasing its memory, like a freed wrapper whose memory
+        // has not been reused yet.
+        unsafe { std::ptr::drop_in_place(wrapper) };
+        assert!(unsafe { scaled_sum(wrapper, 1.0) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+        assert!(unsafe { resolved(wrapper, 1.0) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+        unsafe { my_wrapper_free(wrapper) };
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Invalid or freed handle."
+        );
+        drop(unsafe { Box::from_raw(wrapper as *mut std::mem::ManuallyDrop<MyWrapper>) });
+    }
+
+    #[test]
+    fn test_handle_table() {
+        let first = table_struct_create(DType::F64 as u8, 5.0, 2.0);
+        let second = table_wrapper_create();
+        assert_ne!(first, 0);
+        assert_ne!(first, second);
+        *unsafe { table_wrapper_unwrap(second) }.unwrap() =
+            Box::new(MyStruct::<f64, f64> { a: 1.0, b: 4.0 });
+
+        assert_eq!(unsafe { difference(first) }, 3.0);
+        let resolved = unsafe { difference_resolve(first) }.unwrap();
+        assert_eq!(unsafe { resolved(first) }, 3.0);
+        let handles = [first, second];
+        assert_eq!(unsafe { total(handles.as_ptr(), handles.len()) }, 6.0);
+
+        // Handles of freed wrappers are rejected, even if their slot is reused.
+        table_wrapper_free(first);
+        assert!(unsafe { difference(first) }.is_nan());
+        assert!(c_api_tools::take_last_error()
+            .unwrap()
+            .contains("invalid or freed handle"));
+        assert!(unsafe { resolved(first) }.is_nan());
+        assert!(c_api_tools::take_last_error().is_some());
+        assert!(unsafe { total(handles.as_ptr(), handles.len()) }.is_nan());
+        assert!(c_api_tools::take_last_error().is_some());
+        assert!(!unsafe { difference_supports(first) });
+
+        let third = table_wrapper_create();
+        assert_ne!(third, first);
+        assert!(unsafe { table_wrapper_type_name(first) }.is_null());
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Invalid or freed handle."
+        );
+        table_wrapper_free(first);
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Invalid or freed handle."
+        );
+
+        assert!(unsafe { difference(0) }.is_nan());
+        assert!(c_api_tools::take_last_error().is_some());
+
+        // A wrapper that is freed while it is pinned, for example by another thread, stays
+        // alive until the pin is dropped.
+        let pin = TableWrapper::pin_handle(second).unwrap();
+        table_wrapper_free(second);
+        assert!(unsafe { difference(second) }.is_nan());
+        assert!(c_api_tools::take_last_error().is_some());
+        assert!(unsafe { (*pin.get()).is::<MyStruct<f64, f64>>() });
+        let fourth = table_wrapper_create();
+        drop(pin);
+        assert!(TableWrapper::pin_handle(second).is_none());
+        table_wrapper_free(fourth);
+
+        // The same holds for the reference returned by `_unwrap`.
+        let object = unsafe { table_wrapper_unwrap(third) }.unwrap();
+        table_wrapper_free(third);
+        assert!(object.is_empty());
+        drop(object);
+        assert!(unsafe { table_wrapper_unwrap(third) }.is_none());
+        assert_eq!(
+            c_api_tools::take_last_error().unwrap(),
+            "Invalid or freed handle."
+        );
+    }
 }
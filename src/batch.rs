@@ -0,0 +1,34 @@
+//! Helpers for batched function calls.
+
+/// Call `f` for every index in `0..n`.
+///
+/// If the `parallel` feature is enabled and `parallel` is true, the calls are distributed over
+/// the rayon thread pool. Otherwise they run on the calling thread. `parallel` is only true for
+/// functions with `batched = "parallel"`.
+///
+/// # Safety
+/// If the calls run in parallel, `f` is called from several threads at once even if it is not
+/// `Sync`. The caller must ensure that this is safe if `parallel` is true.
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+pub unsafe fn for_each_index<F: Fn(usize)>(n: usize, parallel: bool, f: F) {
+    #[cfg(feature = "parallel")]
+    if parallel {
+        use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+        struct AssertSync<F>(F);
+        unsafe impl<F> Sync for AssertSync<F> {}
+        unsafe impl<F> Send for AssertSync<F> {}
+
+        impl<F: Fn(usize)> AssertSync<F> {
+            fn call(&self, index: usize) {
+                (self.0)(index)
+            }
+        }
+
+        let f = AssertSync(f);
+        (0..n).into_par_iter().for_each(|index| f.call(index));
+        return;
+    }
+
+    (0..n).for_each(f);
+}
@@ -0,0 +1,127 @@
+//! Runtime borrow tracking for wrapped objects.
+
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+/// Error returned if a value cannot be borrowed because of a conflicting borrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorrowError;
+
+impl std::fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "wrapper already borrowed")
+    }
+}
+
+impl std::error::Error for BorrowError {}
+
+/// Cell that tracks borrows of its value at runtime, like a `RefCell`.
+///
+/// The borrow state is atomic, so conflicting borrows from different threads are detected
+/// as well. A borrow that conflicts with an existing borrow fails instead of blocking.
+pub struct BorrowCell<T> {
+    // The number of shared borrows, or -1 if the value is borrowed mutably.
+    state: AtomicIsize,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for BorrowCell<T> {}
+unsafe impl<T: Send + Sync> Sync for BorrowCell<T> {}
+
+impl<T> BorrowCell<T> {
+    /// Create a new cell.
+    pub fn new(value: T) -> Self {
+        Self {
+            state: AtomicIsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Borrow the value. Fails if the value is borrowed mutably.
+    pub fn try_borrow(&self) -> Result<BorrowRef<'_, T>, BorrowError> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state < 0 {
+                return Err(BorrowError);
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(BorrowRef { cell: self }),
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Borrow the value mutably. Fails if the value is borrowed.
+    pub fn try_borrow_mut(&self) -> Result<BorrowRefMut<'_, T>, BorrowError> {
+        self.state
+            .compare_exchange(0, -1, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| BorrowRefMut { cell: self })
+            .map_err(|_| BorrowError)
+    }
+
+    /// Return true if the value is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        self.state.load(Ordering::Relaxed) != 0
+    }
+
+    /// Return true if the value is borrowed mutably.
+    pub fn is_borrowed_mut(&self) -> bool {
+        self.state.load(Ordering::Relaxed) < 0
+    }
+
+    /// Return a mutable reference to the value. No borrow can exist as the cell is borrowed
+    /// mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+/// Shared borrow of the value of a [`BorrowCell`].
+pub struct BorrowRef<'a, T> {
+    cell: &'a BorrowCell<T>,
+}
+
+impl<T> Deref for BorrowRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for BorrowRef<'_, T> {
+    fn drop(&mut self) {
+        self.cell.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Mutable borrow of the value of a [`BorrowCell`].
+pub struct BorrowRefMut<'a, T> {
+    cell: &'a BorrowCell<T>,
+}
+
+impl<T> Deref for BorrowRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> DerefMut for BorrowRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for BorrowRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.cell.state.store(0, Ordering::Release);
+    }
+}
@@ -0,0 +1,60 @@
+//! Complex numbers with a C compatible layout.
+//!
+//! [`CComplex32`] and [`CComplex64`] have the same size, alignment and layout as the C99 types
+//! `float _Complex` and `double _Complex` and the C++ types `std::complex<float>` and
+//! `std::complex<double>`: the real part is followed by the imaginary part.
+
+use crate::{DType, DTypeIdentifier};
+use num::complex::Complex;
+
+/// Complex number with the layout of `float _Complex`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CComplex32 {
+    /// Real part
+    pub re: f32,
+    /// Imaginary part
+    pub im: f32,
+}
+
+/// Complex number with the layout of `double _Complex`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CComplex64 {
+    /// Real part
+    pub re: f64,
+    /// Imaginary part
+    pub im: f64,
+}
+
+macro_rules! impl_c_complex {
+    ($name:ident, $t:ty, $dtype:expr) => {
+        impl $name {
+            /// Create a new complex number.
+            pub fn new(re: $t, im: $t) -> Self {
+                Self { re, im }
+            }
+        }
+
+        impl From<Complex<$t>> for $name {
+            fn from(value: Complex<$t>) -> Self {
+                Self::new(value.re, value.im)
+            }
+        }
+
+        impl From<$name> for Complex<$t> {
+            fn from(value: $name) -> Self {
+                Complex::new(value.re, value.im)
+            }
+        }
+
+        impl DTypeIdentifier for $name {
+            fn dtype() -> DType {
+                $dtype
+            }
+        }
+    };
+}
+
+impl_c_complex!(CComplex32, f32, DType::C32);
+impl_c_complex!(CComplex64, f64, DType::C64);
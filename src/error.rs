@@ -0,0 +1,155 @@
+//! Error reporting for generated C functions.
+//!
+//! Generated functions do not panic on invalid input. Instead they store an error message
+//! for the calling thread and return the [`ErrorValue`] of their return type. C callers can
+//! retrieve the message with `c_api_tools_last_error`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Store an error message for the current thread.
+pub fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Store an error message for an argument that holds an object of an unsupported type.
+///
+/// `held` is the name of the type of the object and `expected` contains the names of the
+/// supported types. Module paths are removed from all type names.
+pub fn set_type_mismatch_error(arg: &str, held: &str, expected: &[&str]) {
+    let mut names = Vec::<String>::new();
+    for name in expected.iter().map(|name| short_type_name(name)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    set_last_error(&format!(
+        "Argument `{}` holds `{}`, expected one of: {}.",
+        arg,
+        short_type_name(held),
+        names.join(", ")
+    ));
+}
+
+/// Store an error message for arguments that hold objects whose combination of types is
+/// not supported.
+///
+/// `held` contains the names of the arguments and the names of the types of their objects.
+pub fn set_type_combination_error(held: &[(&str, &str)]) {
+    let held = held
+        .iter()
+        .map(|(arg, name)| format!("`{}` holds `{}`", arg, short_type_name(name)))
+        .collect::<Vec<_>>();
+    set_last_error(&format!(
+        "Unsupported combination of types: {}.",
+        held.join(", ")
+    ));
+}
+
+// Remove the module paths from a type name, for example
+// `alloc::vec::Vec<core::option::Option<u8>>` becomes `Vec<Option<u8>>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap());
+            path.clear();
+            short.push(c);
+        }
+    }
+    short.pop();
+    short
+}
+
+/// Return the last error message of the current thread and clear it.
+pub fn take_last_error() -> Option<String> {
+    LAST_ERROR.with(|e| {
+        e.borrow_mut()
+            .take()
+            .map(|message| message.into_string().unwrap())
+    })
+}
+
+/// Return the last error message of the current thread.
+///
+/// Returns a null pointer if no error has occurred since the error was last cleared.
+/// The returned string is valid until the next error occurs on this thread
+/// or the error is cleared.
+#[no_mangle]
+pub extern "C" fn c_api_tools_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Clear the last error message of the current thread.
+#[no_mangle]
+pub extern "C" fn c_api_tools_clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+/// Value returned by a generated function if an error occurs.
+pub trait ErrorValue {
+    /// Return the error value.
+    fn error_value() -> Self;
+}
+
+impl ErrorValue for () {
+    fn error_value() -> Self {}
+}
+
+impl ErrorValue for bool {
+    fn error_value() -> Self {
+        false
+    }
+}
+
+macro_rules! impl_error_value {
+    ($value:expr, $($t:ty),*) => {
+        $(
+            impl ErrorValue for $t {
+                fn error_value() -> Self {
+                    $value
+                }
+            }
+        )*
+    };
+}
+
+impl_error_value!(0, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+impl_error_value!(f32::NAN, f32);
+impl_error_value!(f64::NAN, f64);
+
+impl<T> ErrorValue for *const T {
+    fn error_value() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> ErrorValue for *mut T {
+    fn error_value() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl<T> ErrorValue for Option<T> {
+    fn error_value() -> Self {
+        None
+    }
+}
+
+impl<T> ErrorValue for Result<T, ()> {
+    fn error_value() -> Self {
+        Err(())
+    }
+}
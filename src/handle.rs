@@ -0,0 +1,70 @@
+//! Tags that identify live wrappers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Return a number that identifies a wrapper type with the given name.
+///
+/// This is the 64-bit FNV-1a hash of the name.
+pub const fn type_magic(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        index += 1;
+    }
+    hash
+}
+
+/// Tag at the start of a wrapper that allows to check pointers passed from C.
+///
+/// The tag stores the magic number of the type of the wrapper and a generation that is
+/// unique for every tag. Both are poisoned when the tag is dropped, so pointers to wrappers
+/// of other types and to freed wrappers are detected unless the memory has been reused.
+#[repr(C)]
+pub struct HandleTag {
+    magic: u64,
+    generation: u64,
+}
+
+// The generation of a poisoned tag.
+const POISONED: u64 = 0;
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(POISONED + 1);
+
+impl HandleTag {
+    /// Create a tag with a new generation for the wrapper type with the given magic number.
+    pub fn new(magic: u64) -> Self {
+        Self {
+            magic,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Return the generation of the tag.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Return true if `tag` points to a tag with the given magic number that has not been
+    /// poisoned.
+    ///
+    /// # Safety
+    /// `tag` must be null or point to at least 16 readable bytes that are aligned like a `u64`.
+    pub unsafe fn check(tag: *const HandleTag, magic: u64) -> bool {
+        !tag.is_null()
+            && std::ptr::read_volatile(std::ptr::addr_of!((*tag).magic)) == magic
+            && std::ptr::read_volatile(std::ptr::addr_of!((*tag).generation)) != POISONED
+    }
+}
+
+impl Drop for HandleTag {
+    fn drop(&mut self) {
+        // Volatile writes are not removed even though the memory is freed afterwards.
+        unsafe {
+            std::ptr::write_volatile(&mut self.magic, POISONED);
+            std::ptr::write_volatile(&mut self.generation, POISONED);
+        }
+    }
+}
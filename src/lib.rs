@@ -2,10 +2,58 @@
 #![cfg_attr(feature = "strict", deny(warnings), deny(unused_crate_dependencies))]
 #![warn(missing_docs)]
 
+mod batch;
+mod borrow;
+mod complex;
+mod error;
+mod handle;
+mod object;
+mod scalar;
+mod table;
 mod types;
 
 pub use c_api_tools_macros::cfuncs;
+pub use c_api_tools_macros::concretise_constructor;
 pub use c_api_tools_macros::concretise_types;
 
+pub use borrow::BorrowCell;
+pub use borrow::BorrowError;
+pub use borrow::BorrowRef;
+pub use borrow::BorrowRefMut;
+
+pub use complex::CComplex32;
+pub use complex::CComplex64;
+
+pub use error::c_api_tools_clear_last_error;
+pub use error::c_api_tools_last_error;
+pub use error::set_last_error;
+pub use error::set_type_combination_error;
+pub use error::set_type_mismatch_error;
+pub use error::take_last_error;
+pub use error::ErrorValue;
+
+pub use handle::type_magic;
+pub use handle::HandleTag;
+
+pub use object::static_c_str;
+pub use object::ObjectGuard;
+pub use object::RefCounted;
+pub use object::WrappedObject;
+
+pub use scalar::CScalar;
+pub use scalar::CScalarValue;
+pub use scalar::DTypeMismatch;
+
+pub use table::HandlePin;
+pub use table::HandleTable;
+pub use table::PinnedMut;
+
 pub use types::DType;
 pub use types::DTypeIdentifier;
+pub use types::HasDType;
+
+// Items used by the code that the macros generate. They are not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::batch::for_each_index;
+}
@@ -0,0 +1,134 @@
+//! Objects stored in wrappers.
+
+use std::any::{Any, TypeId};
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// An object stored in a wrapper.
+///
+/// This is implemented for every type. In addition to the functionality of [`Any`], it
+/// allows to query the name of the type of a stored object.
+pub trait WrappedObject: Any {
+    /// Return the name of the type of the object.
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: Any> WrappedObject for T {
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+}
+
+/// A wrapper that is returned to C as a reference counted pointer.
+///
+/// This is implemented by the wrappers of `cfuncs` with the `refcounted` option. Only these
+/// wrappers can be parents in `depends_on`, as the dependant keeps a new owner of the parent.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` must be `refcounted` to be a parent in `depends_on`",
+    label = "not `refcounted`"
+)]
+pub trait RefCounted: Sized {
+    /// Return a new owner of a wrapper that was returned to C.
+    ///
+    /// # Safety
+    /// The pointer must have been returned by `into_raw` and must not be freed.
+    unsafe fn share(ptr: *const Self) -> std::sync::Arc<Self>;
+}
+
+macro_rules! impl_wrapped_object {
+    ($t:ty) => {
+        impl $t {
+            /// Return true if the object has type `T`.
+            pub fn is<T: Any>(&self) -> bool {
+                self.type_id() == TypeId::of::<T>()
+            }
+
+            /// Return true if the object is the placeholder `()` of an empty wrapper.
+            pub fn is_empty(&self) -> bool {
+                self.is::<()>()
+            }
+
+            /// Return true if both objects have the same type.
+            pub fn same_type(&self, other: &dyn WrappedObject) -> bool {
+                self.type_id() == other.type_id()
+            }
+
+            /// Return a reference to the object if it has type `T`.
+            pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+                if self.is::<T>() {
+                    Some(unsafe { &*(self as *const $t as *const T) })
+                } else {
+                    None
+                }
+            }
+
+            /// Return a mutable reference to the object if it has type `T`.
+            pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+                if self.is::<T>() {
+                    Some(unsafe { &mut *(self as *mut $t as *mut T) })
+                } else {
+                    None
+                }
+            }
+
+            /// Move the object out of the box if it has type `T`.
+            pub fn downcast<T: Any>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+                if self.is::<T>() {
+                    Ok(unsafe { Box::from_raw(Box::into_raw(self) as *mut T) })
+                } else {
+                    Err(self)
+                }
+            }
+        }
+    };
+}
+
+impl_wrapped_object!(dyn WrappedObject);
+impl_wrapped_object!(dyn WrappedObject + Send + Sync);
+
+/// Lock guard of a thread-safe wrapper that dereferences to the wrapped object.
+pub struct ObjectGuard<G>(G);
+
+impl<G> ObjectGuard<G> {
+    /// Create a guard from the guard of the lock that holds the object.
+    pub fn new(guard: G) -> Self {
+        Self(guard)
+    }
+}
+
+impl<G: Deref<Target = Box<dyn WrappedObject + Send + Sync>>> Deref for ObjectGuard<G> {
+    type Target = dyn WrappedObject;
+
+    fn deref(&self) -> &Self::Target {
+        &**self.0
+    }
+}
+
+impl<G: DerefMut<Target = Box<dyn WrappedObject + Send + Sync>>> DerefMut for ObjectGuard<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut **self.0
+    }
+}
+
+/// Return a pointer to a null terminated copy of a static string.
+///
+/// Each string is only copied once. The copy is valid until the end of the program, so the
+/// pointer can be returned to C.
+pub fn static_c_str(s: &'static str) -> *const c_char {
+    static STRINGS: Mutex<BTreeMap<&'static str, &'static CStr>> = Mutex::new(BTreeMap::new());
+
+    STRINGS
+        .lock()
+        .unwrap()
+        .entry(s)
+        .or_insert_with(|| {
+            Box::leak(
+                CString::new(s.replace('\0', ""))
+                    .unwrap()
+                    .into_boxed_c_str(),
+            )
+        })
+        .as_ptr()
+}
@@ -0,0 +1,143 @@
+//! Scalars whose type is only known at runtime.
+
+use crate::{set_last_error, CComplex32, CComplex64, DType, DTypeIdentifier, ErrorValue};
+use num::complex::Complex;
+
+/// Value of a [`CScalar`]. The field that is valid is determined by the dtype of the scalar.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union CScalarValue {
+    /// Float 32
+    pub f32: f32,
+    /// Float 64
+    pub f64: f64,
+    /// Complex 32
+    pub c32: CComplex32,
+    /// Complex 64
+    pub c64: CComplex64,
+    /// Unsigned int 8
+    pub u8: u8,
+    /// Unsigned int 32
+    pub u32: u32,
+    /// Unsigned int 64
+    pub u64: u64,
+    /// Int 8
+    pub i8: i8,
+    /// Int 32
+    pub i32: i32,
+    /// Int 64
+    pub i64: i64,
+}
+
+/// Scalar of any type that implements [`DTypeIdentifier`].
+///
+/// Concretised functions can take and return scalars of a generic type as `CScalar`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CScalar {
+    /// Type of the scalar as the discriminant of a [`DType`]. C can set any value, so it is
+    /// only converted by [`CScalar::dtype`].
+    pub dtype: u8,
+    /// Value of the scalar.
+    pub value: CScalarValue,
+}
+
+/// Error returned if a [`CScalar`] is converted to a type that does not match its dtype.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DTypeMismatch {
+    /// The dtype of the type that the scalar was converted to.
+    pub expected: DType,
+    /// The dtype of the scalar.
+    pub found: DType,
+}
+
+impl std::fmt::Display for DTypeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {:?}, found {:?}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for DTypeMismatch {}
+
+impl CScalar {
+    /// Return the dtype of the scalar, or `DType::Undefined` if the dtype is invalid.
+    pub fn dtype(&self) -> DType {
+        DType::from_u8(self.dtype).unwrap_or(DType::Undefined)
+    }
+
+    /// Convert the scalar to the type of an argument.
+    ///
+    /// If the dtype of the scalar does not match `T`, an error is set for the argument
+    /// `arg` and `None` is returned.
+    pub fn convert_argument<T: TryFrom<CScalar, Error = DTypeMismatch>>(
+        self,
+        arg: &str,
+    ) -> Option<T> {
+        match T::try_from(self) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                set_last_error(&format!(
+                    "Argument `{}` has dtype {:?}, expected {:?}.",
+                    arg, e.found, e.expected
+                ));
+                None
+            }
+        }
+    }
+}
+
+impl ErrorValue for CScalar {
+    fn error_value() -> Self {
+        CScalar {
+            dtype: DType::Undefined as u8,
+            value: CScalarValue { u64: 0 },
+        }
+    }
+}
+
+macro_rules! impl_scalar_conversion {
+    ($t:ty, $field:ident, $to_value:expr, $from_value:expr) => {
+        impl From<$t> for CScalar {
+            fn from(value: $t) -> Self {
+                CScalar {
+                    dtype: <$t as DTypeIdentifier>::dtype() as u8,
+                    value: CScalarValue {
+                        $field: $to_value(value),
+                    },
+                }
+            }
+        }
+
+        impl TryFrom<CScalar> for $t {
+            type Error = DTypeMismatch;
+
+            fn try_from(scalar: CScalar) -> Result<Self, Self::Error> {
+                let expected = <$t as DTypeIdentifier>::dtype();
+                if scalar.dtype() == expected {
+                    Ok($from_value(unsafe { scalar.value.$field }))
+                } else {
+                    Err(DTypeMismatch {
+                        expected,
+                        found: scalar.dtype(),
+                    })
+                }
+            }
+        }
+    };
+    ($t:ty, $field:ident) => {
+        impl_scalar_conversion!($t, $field, |value| value, |value| value);
+    };
+}
+
+impl_scalar_conversion!(f32, f32);
+impl_scalar_conversion!(f64, f64);
+impl_scalar_conversion!(Complex<f32>, c32, CComplex32::from, Complex::from);
+impl_scalar_conversion!(Complex<f64>, c64, CComplex64::from, Complex::from);
+impl_scalar_conversion!(CComplex32, c32);
+impl_scalar_conversion!(CComplex64, c64);
+impl_scalar_conversion!(u8, u8);
+impl_scalar_conversion!(u32, u32);
+impl_scalar_conversion!(u64, u64);
+impl_scalar_conversion!(i8, i8);
+impl_scalar_conversion!(i32, i32);
+impl_scalar_conversion!(i64, i64);
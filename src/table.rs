@@ -0,0 +1,187 @@
+//! Tables that map integer handles to wrappers.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// Table that maps integer handles to pointers.
+///
+/// A handle stores the index of a slot in the lower 32 bits and the generation of the slot
+/// in the upper 32 bits. The generation of a slot is increased when its handle is removed,
+/// so handles to removed pointers stay invalid when the slot is reused. The handle 0 is
+/// never valid.
+///
+/// Pointers are accessed through a [`HandlePin`]. A pointer whose handle is removed while it
+/// is pinned is only freed when the last pin is dropped, so a pointer cannot be freed by
+/// another thread while it is in use.
+pub struct HandleTable<T> {
+    slots: Mutex<Slots<T>>,
+    free: unsafe fn(*mut T),
+}
+
+struct Slot<T> {
+    generation: u32,
+    // Null if the slot is free.
+    ptr: *mut T,
+    // The number of pins of the pointer.
+    pins: usize,
+}
+
+struct Slots<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+// The table only stores the pointers. Accessing the pointees is up to the caller.
+unsafe impl<T> Send for HandleTable<T> {}
+unsafe impl<T> Sync for HandleTable<T> {}
+
+impl<T> HandleTable<T> {
+    /// Create an empty table. `free` is called for the pointers of removed handles.
+    pub const fn new(free: unsafe fn(*mut T)) -> Self {
+        Self {
+            slots: Mutex::new(Slots {
+                slots: Vec::new(),
+                free: Vec::new(),
+            }),
+            free,
+        }
+    }
+
+    /// Insert a non-null pointer and return its handle.
+    pub fn insert(&self, ptr: *mut T) -> u64 {
+        assert!(!ptr.is_null(), "Null pointers cannot be inserted.");
+        let mut slots = self.lock();
+        let index = match slots.free.pop() {
+            Some(index) => {
+                slots.slots[index as usize].ptr = ptr;
+                index
+            }
+            None => {
+                let index = u32::try_from(slots.slots.len()).expect("Too many handles.");
+                slots.slots.push(Slot {
+                    generation: 1,
+                    ptr,
+                    pins: 0,
+                });
+                index
+            }
+        };
+        (u64::from(slots.slots[index as usize].generation) << 32) | u64::from(index)
+    }
+
+    /// Pin the pointer of a handle, or return `None` if the handle is invalid or was removed.
+    /// The pointer is not freed while the pin exists.
+    pub fn pin(&self, handle: u64) -> Option<HandlePin<'_, T>> {
+        let mut slots = self.lock();
+        let slot = Self::slot(&mut slots, handle)?;
+        slot.pins += 1;
+        Some(HandlePin {
+            table: self,
+            index: handle as u32,
+            generation: slot.generation,
+            ptr: slot.ptr,
+        })
+    }
+
+    /// Remove a handle from the table and free its pointer once it is no longer pinned.
+    /// Returns false if the handle is invalid or was removed.
+    pub fn remove(&self, handle: u64) -> bool {
+        let mut slots = self.lock();
+        let Some(slot) = Self::slot(&mut slots, handle) else {
+            return false;
+        };
+        // Generation 0 is skipped, so the handle 0 is never valid.
+        slot.generation = slot.generation.checked_add(1).unwrap_or(1);
+        if slot.pins > 0 {
+            return true;
+        }
+        let ptr = Self::release(&mut slots, handle as u32);
+        drop(slots);
+        unsafe { (self.free)(ptr) };
+        true
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Slots<T>> {
+        self.slots
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn slot(slots: &mut Slots<T>, handle: u64) -> Option<&mut Slot<T>> {
+        let slot = slots.slots.get_mut(handle as u32 as usize)?;
+        if slot.ptr.is_null() || u64::from(slot.generation) != handle >> 32 {
+            None
+        } else {
+            Some(slot)
+        }
+    }
+
+    // Make a slot whose handle was removed available again and return its pointer.
+    fn release(slots: &mut Slots<T>, index: u32) -> *mut T {
+        let ptr = std::mem::replace(&mut slots.slots[index as usize].ptr, std::ptr::null_mut());
+        slots.free.push(index);
+        ptr
+    }
+}
+
+/// Pin of a pointer in a [`HandleTable`].
+pub struct HandlePin<'a, T> {
+    table: &'a HandleTable<T>,
+    index: u32,
+    generation: u32,
+    ptr: *mut T,
+}
+
+impl<'a, T> HandlePin<'a, T> {
+    /// Return the pinned pointer.
+    pub fn get(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Return a mutable reference to a part of the pinned pointee that keeps the pin.
+    ///
+    /// # Safety
+    /// The pointee must not be accessed in any other way while the reference exists.
+    pub unsafe fn map_mut<U: ?Sized>(
+        self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> PinnedMut<'a, T, U> {
+        let value = f(&mut *self.ptr) as *mut U;
+        PinnedMut { _pin: self, value }
+    }
+}
+
+impl<T> Drop for HandlePin<'_, T> {
+    fn drop(&mut self) {
+        let mut slots = self.table.lock();
+        let slot = &mut slots.slots[self.index as usize];
+        slot.pins -= 1;
+        // The generation changed if the handle was removed while the pointer was pinned.
+        if slot.pins == 0 && slot.generation != self.generation {
+            let ptr = HandleTable::release(&mut slots, self.index);
+            drop(slots);
+            unsafe { (self.table.free)(ptr) };
+        }
+    }
+}
+
+/// Mutable reference into a pointer of a [`HandleTable`] that is pinned while the reference
+/// exists.
+pub struct PinnedMut<'a, T, U: ?Sized> {
+    _pin: HandlePin<'a, T>,
+    value: *mut U,
+}
+
+impl<T, U: ?Sized> Deref for PinnedMut<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+impl<T, U: ?Sized> DerefMut for PinnedMut<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
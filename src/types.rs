@@ -30,6 +30,27 @@ pub enum DType {
 }
 
 impl DType {
+    /// Return the dtype with the given discriminant, or `None` if there is none.
+    ///
+    /// C passes dtypes as integers, which can have values that are not a dtype.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        [
+            DType::F32,
+            DType::F64,
+            DType::C32,
+            DType::C64,
+            DType::U8,
+            DType::U32,
+            DType::U64,
+            DType::I8,
+            DType::I32,
+            DType::I64,
+            DType::Undefined,
+        ]
+        .into_iter()
+        .find(|dtype| *dtype as u8 == value)
+    }
+
     /// Return true of type is real.
     pub fn is_real(&self) -> bool {
         matches!(
@@ -77,6 +98,17 @@ pub trait DTypeIdentifier {
     fn dtype() -> DType;
 }
 
+/// Objects with a scalar type that implements [`DTypeIdentifier`].
+pub trait HasDType {
+    /// Scalar type of the object.
+    type Scalar: DTypeIdentifier;
+
+    /// Return the runtime numeric type information of the scalar type.
+    fn dtype(&self) -> DType {
+        <Self::Scalar as DTypeIdentifier>::dtype()
+    }
+}
+
 impl DTypeIdentifier for f32 {
     fn dtype() -> DType {
         DType::F32